@@ -1,6 +1,7 @@
 use std::fs;
 use std::io::prelude::*;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 use wsg::build;
 
@@ -50,7 +51,7 @@ static BASIC_TEMPLATE: &'static str = "
     <head>
         <meta charset=\"UTF-8\">
         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">
-        <title>{{ current }}</title>
+        <title>{{ current.title }}</title>
         <link rel=\"stylesheet\" type=\"text/css\" href=\"/static/index.css\" />
     </head>
 
@@ -134,35 +135,81 @@ pub fn generate_new(name: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// Appends a line to the results file
+fn record(line: String) {
+    let mut results = fs::OpenOptions::new()
+        .write(true)
+        .append(true)
+        .open("../results.txt")
+        .unwrap();
+
+    writeln!(results, "{}\n", line).unwrap();
+}
+
+/// Builds a site with `pages` pages `runs` times from scratch, then once more with nothing changed, and reports pages built per second
+fn throughput(pages: usize, runs: usize) {
+    for i in 0..pages {
+        fs::write(format!("content/{}.md", i), FILE).unwrap();
+    }
+
+    let mut total = Duration::new(0, 0);
+    for _run in 0..runs {
+        // the build cache would skip every page after the first run
+        fs::remove_file(".wing-cache").ok();
+        fs::remove_dir_all("site/").unwrap();
+        fs::create_dir("site/").unwrap();
+
+        let build_timing = Instant::now();
+        build(None, None, false).unwrap();
+        total += build_timing.elapsed();
+    }
+
+    let unchanged_timing = Instant::now();
+    build(None, None, false).unwrap();
+    let unchanged = unchanged_timing.elapsed();
+
+    let line = format!(
+        "Throughput ({} pages, {} runs): {:.0} pages/s, unchanged: {:.0} pages/s",
+        pages,
+        runs,
+        (pages * runs) as f64 / total.as_secs_f64(),
+        pages as f64 / unchanged.as_secs_f64()
+    );
+    println!("{}", line);
+    record(line);
+}
+
+/// Usage: `benches [throughput [pages] [runs]]`
 fn main() {
     let mut max = 10;
+    let args: Vec<String> = std::env::args().collect();
 
     match generate_new("benches") {
         Ok(()) => {
             std::env::set_current_dir(&"benches/").unwrap();
+
+            if args.get(1).map_or(false, |a| a == "throughput") {
+                let pages = args.get(2).and_then(|p| p.parse().ok()).unwrap_or(1000);
+                let runs = args.get(3).and_then(|r| r.parse().ok()).unwrap_or(5);
+                throughput(pages, runs);
+                return;
+            }
+
             for _results in 0..=4 {
                 for i in 0..=max {
-                    fs::write(format!("content/{}.md", i), FILE);
+                    fs::write(format!("content/{}.md", i), FILE).unwrap();
                 }
 
-                let build_timing = std::time::Instant::now();
-
-                build(None, None);
+                let build_timing = Instant::now();
 
-                let mut results = fs::OpenOptions::new()
-                    .write(true)
-                    .append(true)
-                    .open("../results.txt")
-                    .unwrap();
+                build(None, None, false).unwrap();
 
-                writeln!(
-                    results,
-                    "Finished ({}): {}ms ({}s)\n",
+                record(format!(
+                    "Finished ({}): {}ms ({}s)",
                     max,
-                    build_timing.elapsed().as_millis()
+                    build_timing.elapsed().as_millis(),
                     build_timing.elapsed().as_secs()
-                )
-                .unwrap();
+                ));
 
                 fs::remove_dir_all("content/").unwrap();
                 fs::create_dir("content/").unwrap();
@@ -170,6 +217,6 @@ fn main() {
                 max = max * 10;
             }
         }
-        Err(e) => panic!(e.to_string()),
+        Err(e) => panic!("{}", e),
     };
 }
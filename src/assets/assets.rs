@@ -0,0 +1,101 @@
+// std
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// external
+use filetime::{set_file_mtime, FileTime};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use walkdir::WalkDir;
+
+// local
+use crate::WingConfig;
+
+/// Builds a matcher from the `staticIgnore` globs
+pub fn ignored(config: &WingConfig) -> std::io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in config.static_ignore.iter() {
+        let glob = Glob::new(pattern)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Checks if `destination` is already a copy of `source`, by comparing sizes and modification times
+fn is_copy(source: &fs::Metadata, destination: &Path) -> bool {
+    match fs::metadata(destination) {
+        Ok(meta) => {
+            meta.len() == source.len()
+                && FileTime::from_last_modification_time(&meta)
+                    == FileTime::from_last_modification_time(source)
+        }
+        Err(_e) => false,
+    }
+}
+
+/// Copies (or hard-links, if `staticLinks` is `true`) the static directory into `static/` in the output directory.  Files matching `staticIgnore` are skipped, as are files that were already copied and haven't changed since.
+///
+/// `optimised` contains the assets already written by the optimiser (at both their original and fingerprinted names), which are skipped.  Returns the locations of all static files in the output directory.
+pub fn copy_static(
+    config: &WingConfig,
+    optimised: &HashMap<String, String>,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut outputs = Vec::new();
+    let static_dir = Path::new(&config.static_dir);
+    let output_dir = Path::new(&config.output_dir).join("static");
+    if static_dir.is_dir() == false {
+        return Ok(outputs);
+    }
+
+    let ignore = ignored(config)?;
+
+    for (original, fingerprinted) in optimised.iter() {
+        outputs.push(output_dir.join(original.trim_start_matches("/static/")));
+        outputs.push(output_dir.join(fingerprinted.trim_start_matches("/static/")));
+    }
+
+    for entry in WalkDir::new(static_dir).min_depth(1) {
+        let entry = entry?;
+        if entry.file_type().is_file() == false {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(static_dir).unwrap();
+        let segments: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if ignore.is_match(relative)
+            || optimised.contains_key(&format!("/static/{}", segments.join("/")))
+        {
+            continue;
+        }
+
+        let destination = output_dir.join(relative);
+        outputs.push(destination.clone());
+
+        let meta = entry.metadata()?;
+        if is_copy(&meta, &destination) {
+            continue;
+        }
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if config.static_links == true {
+            if destination.exists() {
+                fs::remove_file(&destination)?;
+            }
+            fs::hard_link(entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), &destination)?;
+            set_file_mtime(&destination, FileTime::from_last_modification_time(&meta))?;
+        }
+    }
+
+    Ok(outputs)
+}
@@ -0,0 +1,2 @@
+/// Copying static content into the output directory.
+pub mod assets;
@@ -0,0 +1,302 @@
+// std
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+// external
+use serde::{Deserialize, Serialize};
+use tera::Tera;
+
+// local
+use crate::{WingConfig, WingSite};
+
+/// File the build cache is stored in, relative to the project's root
+pub static CACHE_FILE: &'static str = ".wing-cache";
+
+/// Inputs of a rendered page
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CachedPage {
+    /// Hash of the MarkDown (including frontmatter) and its modification time
+    pub content: String,
+    /// Hash of the template used, and all templates it extends, includes or imports
+    pub template: String,
+    /// Hash of the data about other pages that the template shows, if any
+    pub site: String,
+    /// Location of the completed file
+    pub output: String,
+}
+
+/// Cache of the inputs used in the previous build
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BuildCache {
+    /// Hash of the inputs shared by every page (config, assets, etc.)
+    pub global: String,
+    /// Pages from the previous build, by MarkDown path
+    pub pages: HashMap<String, CachedPage>,
+    /// Hashes of each template, including the templates it depends on
+    #[serde(skip)]
+    pub templates: HashMap<String, String>,
+    /// Data about other pages each template shows, including through the templates it depends on
+    #[serde(skip)]
+    pub site_data: HashMap<String, SiteData>,
+    /// Hash of every page's summary and every section
+    #[serde(skip)]
+    pub site: String,
+}
+
+/// Data about other pages a template can show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SiteData {
+    /// Only the page being rendered
+    None,
+    /// The page's section, through `section`, `paginator`, `parent`, `ancestors` or `breadcrumbs`
+    Section,
+    /// Every page, through `items`, `taxonomies` or `__tera_context`
+    All,
+}
+
+/// Variables showing the page's section
+static SECTION_VARIABLES: [&str; 5] =
+    ["section", "paginator", "parent", "ancestors", "breadcrumbs"];
+
+/// Variables showing every page, including the whole context
+static SITE_VARIABLES: [&str; 3] = ["items", "taxonomies", "__tera_context"];
+
+/// Hashes some content
+pub fn hash(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Finds the templates a template depends on, using its `extends`, `include` and `import` tags
+pub fn template_references(source: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{%") {
+        let end = match rest[start..].find("%}") {
+            Some(e) => start + e,
+            None => break,
+        };
+        let tag = rest[start + 2..end].trim_matches(|c: char| c == '-' || c.is_whitespace());
+
+        if tag.starts_with("extends") || tag.starts_with("include") || tag.starts_with("import") {
+            let quoted: Vec<&str> = tag.split(|c| c == '"' || c == '\'').collect();
+            let names = quoted.iter().skip(1).step_by(2);
+            if tag.starts_with("import") {
+                references.extend(names.take(1).map(|n| n.to_string()));
+            } else {
+                references.extend(names.map(|n| n.to_string()));
+            }
+        }
+
+        rest = &rest[end + 2..];
+    }
+
+    references
+}
+
+/// Finds the names used in a template's `{{ }}` and `{% %}` tags, e.g. `items` and `title` from `{% for i in items %}{{ i.title }}{% endfor %}`.  Quoted strings are skipped.
+pub fn template_variables(source: &str) -> HashSet<String> {
+    let mut variables = HashSet::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start..];
+        let closing = if rest.starts_with("{{") {
+            "}}"
+        } else if rest.starts_with("{%") {
+            "%}"
+        } else {
+            rest = &rest[1..];
+            continue;
+        };
+        let end = rest.find(closing).unwrap_or(rest.len());
+
+        let mut quote = None;
+        let mut name = String::new();
+        for c in rest[2..end].chars() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' || c == '`' => quote = Some(c),
+                None if c.is_alphanumeric() || c == '_' => name.push(c),
+                None => {
+                    if name.len() > 0 {
+                        variables.insert(name.clone());
+                    }
+                    name.clear();
+                }
+            }
+        }
+        if name.len() > 0 && quote.is_none() {
+            variables.insert(name);
+        }
+
+        rest = &rest[(end + closing.len()).min(rest.len())..];
+    }
+
+    variables
+}
+
+/// Hashes a template and everything it depends on
+fn template_hash(
+    name: &str,
+    sources: &HashMap<String, String>,
+    visited: &mut HashSet<String>,
+    hashes: &mut Vec<String>,
+) {
+    if visited.insert(String::from(name)) == false {
+        return;
+    }
+
+    match sources.get(name) {
+        Some(source) => {
+            hashes.push(format!("{}:{}", name, hash(source)));
+            for reference in template_references(source) {
+                template_hash(&reference, sources, visited, hashes);
+            }
+        }
+        None => hashes.push(format!("{}:missing", name)),
+    }
+}
+
+impl BuildCache {
+    /// Loads the cache from the previous build, if there is one
+    pub fn load() -> BuildCache {
+        fs::read_to_string(CACHE_FILE)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or(BuildCache {
+                ..Default::default()
+            })
+    }
+
+    /// Computes the hashes for this build.  If the inputs shared by every page have changed since the previous build, all pages are rebuilt.
+    pub fn prepare(
+        &mut self,
+        tera: &Tera,
+        config: &WingConfig,
+        site: &WingSite,
+        assets: &HashMap<String, String>,
+    ) {
+        let mut sources = HashMap::new();
+        for name in tera.templates.keys() {
+            if let Ok(source) = fs::read_to_string(Path::new(&config.template_dir).join(name)) {
+                sources.insert(String::from(name), source);
+            }
+        }
+
+        self.templates.clear();
+        self.site_data.clear();
+        for name in sources.keys() {
+            let mut hashes = Vec::new();
+            let mut chain = HashSet::new();
+            template_hash(name, &sources, &mut chain, &mut hashes);
+            hashes.sort();
+            self.templates
+                .insert(name.clone(), hash(&hashes.join("\n")));
+
+            let variables: HashSet<String> = chain
+                .iter()
+                .filter_map(|t| sources.get(t))
+                .flat_map(|source| template_variables(source))
+                .collect();
+            let data = if SITE_VARIABLES.iter().any(|v| variables.contains(*v)) {
+                SiteData::All
+            } else if SECTION_VARIABLES.iter().any(|v| variables.contains(*v)) {
+                SiteData::Section
+            } else {
+                SiteData::None
+            };
+            self.site_data.insert(name.clone(), data);
+        }
+
+        let mut assets: Vec<String> = assets
+            .iter()
+            .map(|(original, fingerprinted)| format!("{}={}", original, fingerprinted))
+            .collect();
+        assets.sort();
+
+        // only mixed into pages whose templates show other pages, through `items` or `taxonomies`
+        let mut pages: Vec<serde_json::Value> =
+            site.pages.iter().map(|p| serde_json::json!(p)).collect();
+        let mut sections: Vec<&String> = site.sections.keys().collect();
+        sections.sort();
+        for path in sections.into_iter() {
+            let section = &site.sections[path];
+            pages.push(serde_json::json!([
+                section.path,
+                section.title,
+                section.frontmatter
+            ]));
+        }
+
+        self.site = hash(&serde_json::to_string(&pages).unwrap_or_default());
+
+        let global = hash(&format!(
+            "{}\n{}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            serde_json::to_string(config).unwrap_or_default(),
+            assets.join("\n")
+        ));
+
+        if global != self.global {
+            self.pages.clear();
+            self.global = global;
+        }
+    }
+
+    /// Data about other pages that `template` shows.  Unknown templates are assumed to show every page.
+    pub fn site_data(&self, template: &str) -> SiteData {
+        self.site_data
+            .get(template)
+            .cloned()
+            .unwrap_or(SiteData::All)
+    }
+
+    /// Checks if a page's inputs are unchanged since the previous build, and its completed file (and those of any later pages it's paginated into) still exist
+    pub fn is_fresh(&self, path: &str, page: &CachedPage, pagers: &Vec<PathBuf>) -> bool {
+        match self.pages.get(path) {
+            Some(cached) => {
+                cached == page
+                    && Path::new(&page.output).is_file()
+                    && pagers.iter().all(|p| p.is_file())
+            }
+            None => false,
+        }
+    }
+
+    /// Saves the cache for the next build
+    pub fn save(&mut self, pages: HashMap<String, CachedPage>) -> std::io::Result<()> {
+        self.pages = pages;
+        fs::write(CACHE_FILE, serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{template_references, template_variables};
+
+    #[test]
+    pub fn test_template_references() {
+        assert_eq!(
+            template_references(
+                "{% extends \"base.html\" %}{%- include ['nav.html', 'footer.html'] -%}{% import \"macros.html\" as m %}{{ content }}"
+            ),
+            vec!["base.html", "nav.html", "footer.html", "macros.html"]
+        );
+
+        let variables = template_variables(
+            "<section>{% for p in section.pages %}{{ p.title | upper }}{% endfor %}{% include \"items.html\" %}</section>",
+        );
+        assert_eq!(variables.contains("section"), true);
+        assert_eq!(variables.contains("title"), true);
+        assert_eq!(variables.contains("items"), false);
+        assert_eq!(template_variables("{ items }").len(), 0);
+    }
+}
@@ -0,0 +1,2 @@
+/// Build cache, used to only re-render pages whose inputs have changed.
+pub mod cache;
@@ -0,0 +1,191 @@
+// std
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// An error that occurred while building a site
+#[derive(Debug)]
+pub enum WingError {
+    /// The `.wing` configuration file couldn't be parsed
+    Config(String),
+    /// A file or directory couldn't be read or written
+    Io {
+        /// File or directory the error occurred on
+        path: PathBuf,
+        /// Underlying error
+        source: std::io::Error,
+    },
+    /// A page's frontmatter couldn't be parsed
+    Frontmatter {
+        /// MarkDown file the frontmatter is from
+        path: PathBuf,
+        /// Line in the MarkDown file the error occurred on, if known
+        line: Option<usize>,
+        /// Description of the error
+        message: String,
+    },
+    /// A template couldn't be parsed or rendered
+    Template {
+        /// MarkDown file being rendered, if any
+        path: Option<PathBuf>,
+        /// Template the error occurred in, as named by Tera, if known
+        template: Option<String>,
+        /// Line in the template the error occurred on, if known
+        line: Option<usize>,
+        /// Description of the error
+        message: String,
+    },
+    /// A pre- or post-build script couldn't be run, or exited unsuccessfully
+    Script {
+        /// The script that was run
+        script: String,
+        /// Description of the error
+        message: String,
+    },
+    /// A previous build exists, and the build wasn't forced
+    ExistingSite,
+}
+
+impl WingError {
+    /// Creates an IO error for `path`
+    pub fn io(path: &Path, source: std::io::Error) -> WingError {
+        WingError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Creates a template error from a Tera error, including the errors that caused it
+    pub fn template(path: Option<&Path>, error: &tera::Error) -> WingError {
+        let mut message = error.to_string();
+        let mut source = std::error::Error::source(error);
+        while let Some(e) = source {
+            message.push_str(&format!("\n{}", e));
+            source = e.source();
+        }
+
+        // parsing errors contain the location, as ` --> line:column`
+        let line = message
+            .split("--> ")
+            .nth(1)
+            .and_then(|l| l.split(':').next())
+            .and_then(|l| l.trim().parse().ok());
+
+        WingError::Template {
+            path: path.map(|p| p.to_path_buf()),
+            template: template_name(&message),
+            line,
+            message,
+        }
+    }
+}
+
+/// Gets the template named in a Tera error, e.g. `post.html` from `Failed to render 'post.html'`, or `templates/post.html` from `Failed to parse "templates/post.html"`
+fn template_name(message: &str) -> Option<String> {
+    let start = ["Failed to parse ", "Failed to render "]
+        .iter()
+        .filter_map(|prefix| message.find(prefix).map(|i| i + prefix.len()))
+        .min()?;
+    let rest = &message[start..];
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    rest[1..]
+        .find(quote)
+        .map(|end| String::from(&rest[1..end + 1]))
+}
+
+/// Formats a path, with a line number if there is one
+fn location(path: &Path, line: &Option<usize>) -> String {
+    match line {
+        Some(l) => format!("{}:{}", path.display(), l),
+        None => format!("{}", path.display()),
+    }
+}
+
+impl fmt::Display for WingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WingError::Config(message) => write!(f, "Failed to read Wing config: {}", message),
+            WingError::Io { path, source } => {
+                write!(f, "Failed to access {}: {}", path.display(), source)
+            }
+            WingError::Frontmatter {
+                path,
+                line,
+                message,
+            } => write!(
+                f,
+                "Failed to read frontmatter of {}: {}",
+                location(path, line),
+                message
+            ),
+            WingError::Template {
+                path,
+                template,
+                line,
+                message,
+            } => match (path, template) {
+                (Some(p), Some(t)) => write!(
+                    f,
+                    "Failed to render {} for {}: {}",
+                    location(Path::new(t), line),
+                    p.display(),
+                    message
+                ),
+                (Some(p), None) => write!(
+                    f,
+                    "Failed to render template for {}: {}",
+                    p.display(),
+                    message
+                ),
+                (None, Some(t)) => write!(
+                    f,
+                    "Error in template {}: {}",
+                    location(Path::new(t), line),
+                    message
+                ),
+                (None, None) => write!(f, "Failed to parse template(s): {}", message),
+            },
+            WingError::Script { script, message } => {
+                write!(f, "Script \"{}\" failed: {}", script, message)
+            }
+            WingError::ExistingSite => write!(f, "Existing site found, run with -f to force."),
+        }
+    }
+}
+
+impl std::error::Error for WingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WingError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WingError;
+    use std::path::Path;
+    use tera::{Context, Tera};
+
+    #[test]
+    pub fn test_template_errors() {
+        let mut tera = Tera::default();
+        let parse = tera
+            .add_raw_template("post.html", "<p>\n{{ title</p>")
+            .unwrap_err();
+        match WingError::template(None, &parse) {
+            WingError::Template { template, line, .. } => {
+                assert_eq!(template, Some(String::from("post.html")));
+                assert_eq!(line, Some(2));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        tera.add_raw_template("page.html", "{{ missing }}").unwrap();
+        let render = tera.render("page.html", &Context::new()).unwrap_err();
+        let error = WingError::template(Some(Path::new("content/a.md")), &render);
+        assert!(error
+            .to_string()
+            .starts_with("Failed to render page.html for content/a.md:"));
+    }
+}
@@ -0,0 +1,2 @@
+/// Errors returned by the library.
+pub mod error;
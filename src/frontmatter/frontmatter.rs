@@ -0,0 +1,244 @@
+// std
+use std::path::Path;
+
+// external
+use chrono::prelude::{DateTime, NaiveDate, Utc};
+use serde_json::{Map, Number, Value};
+
+// local
+use crate::error::error::WingError;
+use crate::WingTemplateFrontmatter;
+
+/// Format of a frontmatter block
+#[derive(Debug, PartialEq)]
+pub enum FrontmatterFormat {
+    /// Delimited by `---`
+    Yaml,
+    /// Delimited by `+++`
+    Toml,
+}
+
+/// Splits a MarkDown file into its frontmatter block (if any) and the remaining MarkDown
+pub fn split(content: &str) -> (Option<(FrontmatterFormat, &str)>, &str) {
+    let content = content.trim_start_matches('\u{feff}');
+    let (format, delimiter) = if content.starts_with("---") {
+        (FrontmatterFormat::Yaml, "---")
+    } else if content.starts_with("+++") {
+        (FrontmatterFormat::Toml, "+++")
+    } else {
+        return (None, content);
+    };
+
+    let mut start = match content.find('\n') {
+        Some(i) if content[..i].trim_end() == delimiter => i + 1,
+        _ => return (None, content),
+    };
+    let block_start = start;
+
+    while start < content.len() {
+        let end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |i| start + i + 1);
+        if content[start..end].trim_end() == delimiter {
+            return (
+                Some((format, &content[block_start..start])),
+                &content[end..],
+            );
+        }
+        start = end;
+    }
+
+    (None, content)
+}
+
+/// Converts a TOML value to JSON, so dates end up as strings
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(Number::from(i)),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(a) => Value::Array(a.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(t) => Value::Object(
+            t.into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
+/// Parses a frontmatter block from `path`.  Line numbers in errors are relative to the start of the file.
+pub fn parse(
+    format: &FrontmatterFormat,
+    raw: &str,
+    path: &Path,
+) -> std::result::Result<WingTemplateFrontmatter, WingError> {
+    if raw.trim().len() == 0 {
+        return Ok(WingTemplateFrontmatter {
+            ..Default::default()
+        });
+    }
+
+    // the block starts after the opening delimiter
+    let error = |line: Option<usize>, message: String| WingError::Frontmatter {
+        path: path.to_path_buf(),
+        line: line.map(|l| l + 1),
+        message,
+    };
+
+    let frontmatter: WingTemplateFrontmatter = match format {
+        FrontmatterFormat::Yaml => serde_yaml::from_str(raw)
+            .map_err(|e| error(e.location().map(|l| l.line()), e.to_string()))?,
+        FrontmatterFormat::Toml => {
+            let value: toml::Value = toml::from_str(raw)
+                .map_err(|e| error(e.line_col().map(|(l, _)| l + 1), e.to_string()))?;
+            serde_json::from_value(toml_to_json(value)).map_err(|e| error(None, e.to_string()))?
+        }
+    };
+
+    check_locations(&frontmatter).map_err(|message| error(None, message))?;
+    Ok(frontmatter)
+}
+
+/// Checks that `slug` and `aliases` stay inside the output directory: slugs can't contain separators, and neither can step up a directory
+fn check_locations(frontmatter: &WingTemplateFrontmatter) -> std::result::Result<(), String> {
+    if let Some(slug) = &frontmatter.slug {
+        if slug.contains('/') || slug.contains('\\') || slug == "." || slug == ".." {
+            return Err(format!("slug \"{}\" must be a single file name", slug));
+        }
+    }
+
+    for alias in frontmatter.aliases.iter() {
+        if alias.split(|c| c == '/' || c == '\\').any(|s| s == "..") {
+            return Err(format!("alias \"{}\" can't contain \"..\"", alias));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a frontmatter date, written as `YYYY-MM-DD` (midnight UTC) or RFC 3339
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(date) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(parsed) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Some(DateTime::<Utc>::from_utc(parsed.and_hms(0, 0, 0), Utc));
+    }
+    None
+}
+
+/// Checks whether a page should be published at `now`: it isn't a draft, its `publishDate` has passed and its `expiryDate` hasn't.  Dates that can't be parsed are ignored.
+pub fn is_published(frontmatter: &WingTemplateFrontmatter, now: DateTime<Utc>) -> bool {
+    if frontmatter.draft == true {
+        return false;
+    }
+
+    let publish = frontmatter.publish_date.as_deref().and_then(parse_date);
+    let expiry = frontmatter.expiry_date.as_deref().and_then(parse_date);
+    publish.map_or(true, |d| d <= now) && expiry.map_or(true, |d| d > now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_published, parse, parse_date, split, FrontmatterFormat};
+    use crate::WingTemplateFrontmatter;
+    use std::path::Path;
+
+    #[test]
+    pub fn test_split() {
+        let (frontmatter, body) = split("---\ntitle: Hello\n---\n# Hello\n");
+        assert_eq!(
+            frontmatter,
+            Some((FrontmatterFormat::Yaml, "title: Hello\n"))
+        );
+        assert_eq!(body, "# Hello\n");
+
+        let (frontmatter, body) = split("# Hello\n\n---\n");
+        assert_eq!(frontmatter, None);
+        assert_eq!(body, "# Hello\n\n---\n");
+    }
+
+    #[test]
+    pub fn test_parse() {
+        let yaml = parse(
+            &FrontmatterFormat::Yaml,
+            "title: Hello\ntags: [a, b]\nauthor: me\n",
+            Path::new("a.md"),
+        )
+        .unwrap();
+        assert_eq!(yaml.title, Some(String::from("Hello")));
+        assert_eq!(yaml.tags, vec!["a", "b"]);
+        assert_eq!(yaml.extra["author"], "me");
+
+        let toml = parse(
+            &FrontmatterFormat::Toml,
+            "title = \"Hello\"\ndate = 2020-10-01\ndraft = true\n",
+            Path::new("a.md"),
+        )
+        .unwrap();
+        assert_eq!(toml.date, Some(String::from("2020-10-01")));
+        assert_eq!(toml.draft, true);
+
+        match parse(
+            &FrontmatterFormat::Yaml,
+            "title: Hello\ntags: [a\n",
+            Path::new("a.md"),
+        ) {
+            Err(e) => assert!(e
+                .to_string()
+                .starts_with("Failed to read frontmatter of a.md:")),
+            Ok(_) => panic!("invalid frontmatter was parsed"),
+        }
+
+        for raw in [
+            "slug: ../../escaped",
+            "slug: /etc/x",
+            "aliases: [/old/../../x]",
+        ]
+        .iter()
+        {
+            assert!(parse(&FrontmatterFormat::Yaml, raw, Path::new("a.md")).is_err());
+        }
+        assert!(parse(
+            &FrontmatterFormat::Yaml,
+            "slug: renamed\naliases: [/old/post, /old/]\n",
+            Path::new("a.md")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    pub fn test_published() {
+        assert_eq!(parse_date("2020-01-02"), parse_date("2020-01-02T00:00:00Z"));
+        assert_eq!(parse_date("soon"), None);
+
+        let now = parse_date("2020-06-01").unwrap();
+        let page =
+            |draft: bool, publish: Option<&str>, expiry: Option<&str>| WingTemplateFrontmatter {
+                draft,
+                publish_date: publish.map(String::from),
+                expiry_date: expiry.map(String::from),
+                ..Default::default()
+            };
+        assert_eq!(is_published(&page(false, None, None), now), true);
+        assert_eq!(is_published(&page(true, None, None), now), false);
+        assert_eq!(
+            is_published(&page(false, Some("2020-05-01"), None), now),
+            true
+        );
+        assert_eq!(
+            is_published(&page(false, Some("2020-07-01"), None), now),
+            false
+        );
+        assert_eq!(
+            is_published(&page(false, None, Some("2020-06-01")), now),
+            false
+        );
+        assert_eq!(
+            is_published(&page(false, None, Some("2021-01-01")), now),
+            true
+        );
+    }
+}
@@ -0,0 +1,2 @@
+/// Parsing for YAML (`---`) and TOML (`+++`) frontmatter blocks.
+pub mod frontmatter;
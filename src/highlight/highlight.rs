@@ -0,0 +1,339 @@
+// std
+use std::sync::RwLock;
+
+// external
+use lazy_static::lazy_static;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::html::{
+    css_for_theme_with_class_style, line_tokens_to_classed_spans, styled_line_to_highlighted_html,
+    ClassStyle, IncludeBackground,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+// local
+use crate::{WingError, WingHighlightConfig};
+
+/// Prefix of the classes used with `classes`, so they don't clash with the site's own
+static CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+lazy_static! {
+    static ref SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static ref THEMES: ThemeSet = ThemeSet::load_defaults();
+    /// Theme used when highlighting with inline styles, loaded at the start of each build
+    static ref THEME: RwLock<Theme> = RwLock::new(Theme::default());
+}
+
+/// Options for a code block, from its fence, e.g. ```` ```rust,linenos,hl_lines=1 3-5 ````
+#[derive(Debug, PartialEq)]
+pub struct Fence {
+    /// Language to highlight the code as, if given
+    pub language: Option<String>,
+    /// If `true`, lines are numbered
+    pub line_numbers: bool,
+    /// Ranges of lines (from `1`, inclusive) to highlight
+    pub highlighted: Vec<(usize, usize)>,
+}
+
+impl Fence {
+    /// Parses a fence's info string.  `linenos` numbers lines, and `hl_lines` takes space-separated lines and ranges to highlight.
+    pub fn parse(info: &str, config: &WingHighlightConfig) -> Fence {
+        let mut fence = Fence {
+            language: None,
+            line_numbers: config.line_numbers,
+            highlighted: vec![],
+        };
+
+        for (i, option) in info.split(',').map(|o| o.trim()).enumerate() {
+            if option == "linenos" {
+                fence.line_numbers = true;
+            } else if option.starts_with("hl_lines=") {
+                fence.highlighted = option
+                    .trim_start_matches("hl_lines=")
+                    .split_whitespace()
+                    .filter_map(|range| {
+                        let mut bounds = range.splitn(2, '-').map(|b| b.parse::<usize>());
+                        match (bounds.next(), bounds.next()) {
+                            (Some(Ok(start)), None) => Some((start, start)),
+                            (Some(Ok(start)), Some(Ok(end))) => Some((start, end)),
+                            _ => None,
+                        }
+                    })
+                    .collect();
+            } else if i == 0 && option.len() > 0 {
+                fence.language = Some(String::from(option));
+            }
+        }
+
+        fence
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted
+            .iter()
+            .any(|(start, end)| line >= *start && line <= *end)
+    }
+}
+
+/// Finds a theme by name, from the built-in themes or a `.tmTheme` file relative to the project
+fn find_theme(name: &str) -> std::result::Result<Theme, WingError> {
+    if let Some(theme) = THEMES.themes.get(name) {
+        return Ok(theme.clone());
+    }
+
+    if name.ends_with(".tmTheme") {
+        return ThemeSet::get_theme(name)
+            .map_err(|e| WingError::Config(format!("Failed to load theme {}: {}", name, e)));
+    }
+
+    let mut names: Vec<&String> = THEMES.themes.keys().collect();
+    names.sort();
+    Err(WingError::Config(format!(
+        "Unknown highlight theme \"{}\", use a .tmTheme file or one of: {}",
+        name,
+        names
+            .iter()
+            .map(|n| n.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    )))
+}
+
+/// Loads the configured theme, for `highlight` to use
+pub fn load_theme(config: &WingHighlightConfig) -> std::result::Result<(), WingError> {
+    *THEME.write().unwrap() = find_theme(&config.theme)?;
+    Ok(())
+}
+
+fn css_color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// Background for highlighted lines, falling back to a translucent grey for themes without one
+fn line_highlight(theme: &Theme) -> String {
+    theme
+        .settings
+        .line_highlight
+        .map(css_color)
+        .unwrap_or(String::from("rgba(128,128,128,0.2)"))
+}
+
+/// Line number colour, falling back to the text colour
+fn gutter(theme: &Theme) -> String {
+    theme
+        .settings
+        .gutter_foreground
+        .or(theme.settings.foreground)
+        .map(css_color)
+        .unwrap_or(String::from("inherit"))
+}
+
+/// Gets the CSS for a theme, for code highlighted with `classes`
+pub fn theme_css(name: &str) -> std::result::Result<String, WingError> {
+    let theme = find_theme(name)?;
+    let mut css = css_for_theme_with_class_style(&theme, CLASS_STYLE)
+        .map_err(|e| WingError::Config(format!("Failed to generate CSS for {}: {}", name, e)))?;
+    css.push_str(&format!(
+        "\n.hl-line-number {{\n color: {};\n padding-right: 1em;\n user-select: none;\n}}\n\n.hl-highlighted {{\n background-color: {};\n display: inline-block;\n width: 100%;\n}}\n",
+        gutter(&theme),
+        line_highlight(&theme)
+    ));
+    Ok(css)
+}
+
+/// Highlights a line with classes, reopening the spans left open by earlier lines so each line can be wrapped on its own
+fn classed_line(
+    line: &str,
+    parser: &mut ParseState,
+    stack: &mut ScopeStack,
+) -> std::result::Result<String, syntect::Error> {
+    let mut html = String::new();
+    for scope in stack.as_slice() {
+        let classes: Vec<String> = scope
+            .build_string()
+            .split('.')
+            .map(|atom| format!("hl-{}", atom))
+            .collect();
+        html.push_str(&format!("<span class=\"{}\">", classes.join(" ")));
+    }
+
+    let ops = parser.parse_line(line, &SYNTAXES)?;
+    let (spans, _delta) = line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, stack)?;
+    html.push_str(&spans);
+    html.push_str(&"</span>".repeat(stack.len()));
+    Ok(html)
+}
+
+/// Highlights a code block, wrapping each line in a `<span class="hl-line">`
+pub fn highlight(code: &str, fence: &Fence, config: &WingHighlightConfig) -> String {
+    let syntax: &SyntaxReference = fence
+        .language
+        .as_deref()
+        .and_then(|l| SYNTAXES.find_syntax_by_token(l))
+        .unwrap_or(SYNTAXES.find_syntax_plain_text());
+    let theme = THEME.read().unwrap();
+
+    let mut html = if config.classes == true {
+        String::from("<pre class=\"hl-code\">")
+    } else {
+        format!(
+            "<pre style=\"background-color:{};color:{};\">",
+            theme
+                .settings
+                .background
+                .map(css_color)
+                .unwrap_or(String::from("inherit")),
+            theme
+                .settings
+                .foreground
+                .map(css_color)
+                .unwrap_or(String::from("inherit"))
+        )
+    };
+    match &fence.language {
+        Some(language) => html.push_str(&format!(
+            "<code class=\"language-{}\">",
+            language.replace('"', "&quot;")
+        )),
+        None => html.push_str("<code>"),
+    };
+
+    let mut styled = HighlightLines::new(syntax, &theme);
+    let mut parser = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    for (i, line) in LinesWithEndings::from(code).enumerate() {
+        let number = i + 1;
+        let mut highlighted = if config.classes == true {
+            classed_line(line, &mut parser, &mut stack)
+        } else {
+            styled
+                .highlight_line(line, &SYNTAXES)
+                .and_then(|ranges| styled_line_to_highlighted_html(&ranges, IncludeBackground::No))
+        }
+        // syntect only fails on broken syntax definitions, so fall back to the plain line
+        .unwrap_or_else(|_e| {
+            line.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        });
+        // the newline goes between lines, so highlighted lines don't take up two
+        if let Some(newline) = highlighted.rfind('\n') {
+            highlighted.remove(newline);
+        }
+
+        match (fence.is_highlighted(number), config.classes) {
+            (true, true) => html.push_str("<span class=\"hl-line hl-highlighted\">"),
+            (true, false) => html.push_str(&format!(
+                "<span class=\"hl-line\" style=\"background-color:{};display:inline-block;width:100%;\">",
+                line_highlight(&theme)
+            )),
+            (false, _) => html.push_str("<span class=\"hl-line\">"),
+        };
+        if fence.line_numbers == true {
+            if config.classes == true {
+                html.push_str(&format!("<span class=\"hl-line-number\">{}</span>", number));
+            } else {
+                html.push_str(&format!(
+                    "<span class=\"hl-line-number\" style=\"color:{};padding-right:1em;user-select:none;\">{}</span>",
+                    gutter(&theme),
+                    number
+                ));
+            }
+        }
+        html.push_str(&highlighted);
+        html.push_str("</span>\n");
+    }
+
+    html.push_str("</code></pre>\n");
+    html
+}
+
+/// Replaces fenced and indented code blocks with highlighted HTML
+pub fn code_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    config: &WingHighlightConfig,
+) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut block: Option<(Fence, String)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info,
+                    CodeBlockKind::Indented => CowStr::from(""),
+                };
+                block = Some((Fence::parse(&info, config), String::new()));
+            }
+            Event::Text(text) if block.is_some() => {
+                if let Some((_fence, code)) = block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_kind)) => {
+                if let Some((fence, code)) = block.take() {
+                    output.push(Event::Html(CowStr::from(highlight(&code, &fence, config))));
+                }
+            }
+            e => output.push(e),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{code_blocks, load_theme, theme_css, Fence};
+    use crate::WingHighlightConfig;
+    use pulldown_cmark::{html, Parser};
+
+    #[test]
+    pub fn test_highlight() {
+        let config = WingHighlightConfig {
+            ..Default::default()
+        };
+        assert_eq!(
+            Fence::parse("rust,linenos,hl_lines=1 3-5 x", &config),
+            Fence {
+                language: Some(String::from("rust")),
+                line_numbers: true,
+                highlighted: vec![(1, 1), (3, 5)],
+            }
+        );
+        assert_eq!(Fence::parse("", &config).language, None);
+
+        load_theme(&config).unwrap();
+        let markdown = "```rust,hl_lines=2\nfn main() {\n    let a = \"<b>\";\n}\n```\n";
+        let mut output = String::new();
+        html::push_html(
+            &mut output,
+            code_blocks(Parser::new(markdown), &config).into_iter(),
+        );
+        assert!(output.starts_with("<pre style=\"background-color:#"));
+        assert!(output.contains("<code class=\"language-rust\">"));
+        assert!(output.contains("&lt;b&gt;"));
+        assert_eq!(output.matches("class=\"hl-line\"").count(), 3);
+        assert_eq!(output.matches("display:inline-block").count(), 1);
+
+        let classed = WingHighlightConfig {
+            classes: true,
+            line_numbers: true,
+            ..Default::default()
+        };
+        let mut output = String::new();
+        html::push_html(
+            &mut output,
+            code_blocks(Parser::new("```rust\n/* a\nb */\n```\n"), &classed).into_iter(),
+        );
+        assert!(output.contains("<span class=\"hl-line-number\">2</span><span class=\"hl-source hl-rust\"><span class=\"hl-comment"));
+        assert_eq!(
+            output.matches("<span").count(),
+            output.matches("</span>").count()
+        );
+
+        assert!(theme_css("base16-ocean.dark").unwrap().contains(".hl-code"));
+        assert!(theme_css("missing").is_err());
+    }
+}
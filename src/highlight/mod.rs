@@ -0,0 +1,2 @@
+/// Syntax highlighting for fenced code blocks.
+pub mod highlight;
@@ -1,9 +1,11 @@
 //! Wing core
 // std
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{stdout, BufWriter, Write};
-use std::path::Path;
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::RwLock;
 use std::time::SystemTime;
 
 // external
@@ -15,7 +17,7 @@ use crossterm::{
     Result,
 };
 use lazy_static::lazy_static;
-use pulldown_cmark::{html, CowStr, Event, Options, Parser};
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
@@ -23,9 +25,30 @@ use tera::{Context, Tera};
 use walkdir::WalkDir;
 
 // local
+pub mod assets;
+pub mod cache;
+pub mod error;
+pub mod frontmatter;
+pub mod highlight;
+pub mod links;
+pub mod optimise;
+pub mod pagination;
+pub mod rss;
+pub mod sections;
+pub mod shortcodes;
+pub mod sitemap;
+pub mod taxonomies;
+pub mod toc;
+
+use cache::cache::{BuildCache, CachedPage, SiteData};
+pub use error::error::WingError;
+use pagination::pagination::WingPaginator;
+use sections::sections::{WingPageLink, WingSection, SECTION_INDEX};
+use taxonomies::taxonomies::WingTaxonomy;
+use toc::toc::WingHeading;
 
 /// Represents a Wing configuration file
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase", default)]
 pub struct WingConfig {
     /// If `true`, generates an RSS feed
@@ -42,6 +65,37 @@ pub struct WingConfig {
     pub pre_scripts: Vec<String>,
     /// Scripts to run after building. This can also run other build tools.
     pub post_scripts: Vec<String>,
+    /// URL the built site will be hosted at, e.g. `https://example.com`
+    pub base_url: String,
+    /// Values: `file`, `directory`
+    /// With `directory`, `a/b.md` is built to `a/b/index.html` so it's found at `/a/b/`, instead of `a/b.html`
+    pub url_style: String,
+    /// Number of pages listed on each page of section and taxonomy term pages, or `0` to list them all on one page
+    pub paginate_by: usize,
+    /// RSS feed settings, only used if `rss` is `true`
+    pub feed: WingFeedConfig,
+    /// Syntax highlighting settings for code blocks
+    pub highlight: WingHighlightConfig,
+    /// If `true`, headings get a `#` link to themselves
+    pub heading_anchors: bool,
+    /// Directory containing the MarkDown content
+    pub content_dir: String,
+    /// Directory containing the templates
+    pub template_dir: String,
+    /// Directory the built site is written to
+    pub output_dir: String,
+    /// Directory containing static content (styling, scripts, etc.)
+    pub static_dir: String,
+    /// Globs of files in the static directory that aren't copied to the output directory, e.g. `**/*.scss`
+    pub static_ignore: Vec<String>,
+    /// If `true`, static files are hard-linked into the output directory instead of copied
+    pub static_links: bool,
+    /// Taxonomies pages are grouped by, from lists in their frontmatter, e.g. `tags`
+    pub taxonomies: Vec<WingTaxonomyConfig>,
+    /// Globs of files that `serve` doesn't rebuild the site for, relative to the project, e.g. `drafts/**`
+    pub watch_ignore: Vec<String>,
+    /// Template for `404.html`, used if there's no `404.md` in the content directory
+    pub not_found_template: String,
 }
 
 impl Default for WingConfig {
@@ -53,6 +107,97 @@ impl Default for WingConfig {
             optimisation_level: String::from("none"),
             pre_scripts: vec![],
             post_scripts: vec![],
+            base_url: String::new(),
+            url_style: String::from("file"),
+            paginate_by: 0,
+            feed: WingFeedConfig {
+                ..Default::default()
+            },
+            highlight: WingHighlightConfig {
+                ..Default::default()
+            },
+            heading_anchors: false,
+            content_dir: String::from("content"),
+            template_dir: String::from("templates"),
+            output_dir: String::from("site"),
+            static_dir: String::from("static"),
+            static_ignore: vec![],
+            static_links: false,
+            taxonomies: vec![],
+            watch_ignore: vec![],
+            not_found_template: String::new(),
+        }
+    }
+}
+
+/// Syntax highlighting settings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WingHighlightConfig {
+    /// If `true`, code blocks are highlighted when building
+    pub enabled: bool,
+    /// Name of a built-in theme, or path to a `.tmTheme` file
+    pub theme: String,
+    /// If `true`, code is highlighted with classes instead of inline styles, styled with the CSS from `wsg highlight-css`
+    pub classes: bool,
+    /// If `true`, every code block's lines are numbered, not only those with `linenos`
+    pub line_numbers: bool,
+}
+
+impl Default for WingHighlightConfig {
+    fn default() -> Self {
+        WingHighlightConfig {
+            enabled: false,
+            theme: String::from("base16-ocean.dark"),
+            classes: false,
+            line_numbers: false,
+        }
+    }
+}
+
+/// A taxonomy, e.g. `tags` or `categories`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WingTaxonomyConfig {
+    /// Name of the taxonomy, which is also the frontmatter key it's read from and the directory its pages are built to
+    pub name: String,
+    /// If `true`, each term gets its own RSS feed
+    pub feed: bool,
+    /// Number of pages listed on each page of a term's page, instead of `paginateBy`
+    pub paginate_by: Option<usize>,
+}
+
+impl Default for WingTaxonomyConfig {
+    fn default() -> Self {
+        WingTaxonomyConfig {
+            name: String::new(),
+            feed: false,
+            paginate_by: None,
+        }
+    }
+}
+
+/// RSS feed settings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WingFeedConfig {
+    /// Title of the feed's channel
+    pub title: String,
+    /// Description of the feed's channel
+    pub description: String,
+    /// Maximum number of items in the feed, `0` for no limit
+    pub limit: usize,
+    /// Content subdirectories whose pages are included in the feed.  If empty, all pages are included.
+    pub sections: Vec<String>,
+}
+
+impl Default for WingFeedConfig {
+    fn default() -> Self {
+        WingFeedConfig {
+            title: String::new(),
+            description: String::new(),
+            limit: 20,
+            sections: vec![],
         }
     }
 }
@@ -75,25 +220,314 @@ impl WingConfig {
 
 /// Custom templating data
 #[derive(Serialize)]
-pub struct WingTemplateData {
+pub struct WingTemplateData<'a> {
     /// Raw MarkDown
     pub content: String,
-    /// List of item paths
-    pub items: Vec<String>,
-    /// current item
-    pub current: String,
+    /// The page's headings, nested by level
+    pub toc: Vec<WingHeading>,
+    /// All pages, sorted by path.  Left out if the template doesn't use it, as it grows with the site.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<&'a Vec<WingPageSummary>>,
+    /// current page
+    pub current: &'a WingPageSummary,
+    /// Section the page is in, or the section itself on a section's listing page.  Left out if the template doesn't use it.
+    pub section: Option<&'a WingSection>,
+    /// Section above the page, if there is one
+    pub parent: Option<WingPageLink>,
+    /// Sections above the page, from the top-level section down
+    pub ancestors: Vec<WingPageLink>,
+    /// `ancestors`, followed by the page itself
+    pub breadcrumbs: Vec<WingPageLink>,
+    /// All taxonomies, by name.  Left out if the template doesn't use it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taxonomies: Option<&'a HashMap<String, WingTaxonomy>>,
+    /// On a paginated section's listing page, the pages listed on it and links to the others
+    pub paginator: Option<WingPaginator>,
     /// frontmatter
     pub frontmatter: WingTemplateFrontmatter,
     /// Last time file was modified
     pub modified: String,
     /// Time file was created
     pub created: String,
+    /// Canonical URL of the page
+    pub url: String,
 }
 
+/// Frontmatter, from a YAML (`---`) or TOML (`+++`) block at the start of a MarkDown file
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct WingTemplateFrontmatter {
     /// template to use
     pub template: String,
+    /// title of the page
+    pub title: Option<String>,
+    /// short summary of the page
+    pub description: Option<String>,
+    /// publication date, as `YYYY-MM-DD` or RFC 3339
+    pub date: Option<String>,
+    /// tags for the page
+    pub tags: Vec<String>,
+    /// if `true`, the page is a draft, and is only built with `--drafts`
+    pub draft: bool,
+    /// the page is only built from this date, as `YYYY-MM-DD` or RFC 3339
+    #[serde(rename = "publishDate")]
+    pub publish_date: Option<String>,
+    /// the page is only built until this date, as `YYYY-MM-DD` or RFC 3339
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: Option<String>,
+    /// file name to use for the page, instead of the MarkDown file's name
+    pub slug: Option<String>,
+    /// other paths that should redirect to the page
+    pub aliases: Vec<String>,
+    /// in a section's `_index.md`, the number of pages listed on each page of its listing, instead of `paginateBy`
+    pub paginate_by: Option<usize>,
+    /// sitemap `changefreq` value, e.g. `weekly`
+    pub changefreq: Option<String>,
+    /// sitemap `priority` value, from `0.0` to `1.0`
+    pub priority: Option<f32>,
+    /// if `false`, the page is left out of the sitemap
+    pub sitemap: bool,
+    /// any other keys
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Default for WingTemplateFrontmatter {
+    fn default() -> Self {
+        WingTemplateFrontmatter {
+            template: String::new(),
+            title: None,
+            description: None,
+            date: None,
+            tags: vec![],
+            draft: false,
+            publish_date: None,
+            expiry_date: None,
+            slug: None,
+            aliases: vec![],
+            paginate_by: None,
+            changefreq: None,
+            priority: None,
+            sitemap: true,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// Summary of a page, as given to templates in `items` and `current`
+#[derive(Serialize, Clone, Debug)]
+pub struct WingPageSummary {
+    /// Name of the content file, relative to the content directory and without its extension, e.g. `blog/post`
+    pub path: String,
+    /// Root-relative link to the page, e.g. `/blog/post.html`, which is rewritten like other links in templates
+    pub link: String,
+    /// Canonical URL of the page
+    pub url: String,
+    /// `title` from the frontmatter, or the file's name
+    pub title: String,
+    /// frontmatter
+    pub frontmatter: WingTemplateFrontmatter,
+    /// Time file was created
+    pub created: String,
+    /// Last time file was modified
+    pub modified: String,
+    /// Number of words in the page's MarkDown
+    pub word_count: usize,
+    /// Directory the page is in, relative to the content directory, e.g. `blog` (empty at the top level)
+    pub section: String,
+}
+
+#[cfg(test)]
+impl WingPageSummary {
+    /// A summary of the page at `path`, e.g. `blog/post`, in the section its path is in
+    pub fn test(path: &str) -> WingPageSummary {
+        WingPageSummary {
+            path: String::from(path),
+            link: format!("/{}.html", path),
+            url: format!("/{}.html", path),
+            title: String::from(path),
+            frontmatter: WingTemplateFrontmatter {
+                ..Default::default()
+            },
+            created: String::new(),
+            modified: String::new(),
+            word_count: 0,
+            section: String::from(path.rsplitn(2, '/').nth(1).unwrap_or("")),
+        }
+    }
+}
+
+/// Pages and sections of the site, available to every template
+pub struct WingSite {
+    /// Summaries of all pages, sorted by path
+    pub pages: Vec<WingPageSummary>,
+    /// Sections, by path relative to the content directory
+    pub sections: HashMap<String, WingSection>,
+    /// Taxonomies, by name
+    pub taxonomies: HashMap<String, WingTaxonomy>,
+}
+
+/// A content file, read before any pages are rendered so every page can be summarised
+pub struct WingSource {
+    /// Path to raw MarkDown
+    pub path: PathBuf,
+    /// For a section's listing page, the section's path relative to the content directory
+    pub section: Option<String>,
+    /// Template to render with, without the extension
+    pub template: String,
+    /// Raw MarkDown, including frontmatter
+    pub content: String,
+    /// frontmatter
+    pub frontmatter: WingTemplateFrontmatter,
+    /// Where the page is built to
+    pub output: PathBuf,
+    /// Time file was created
+    pub created: DateTime<Utc>,
+    /// Last time file was modified
+    pub modified: DateTime<Utc>,
+    /// Summary of the page, for templates
+    pub summary: WingPageSummary,
+}
+
+impl WingSource {
+    pub fn read(path: &Path, config: &WingConfig) -> std::result::Result<WingSource, WingError> {
+        let content = fs::read_to_string(path).map_err(|e| WingError::io(path, e))?;
+
+        let (raw_frontmatter, markdown) = frontmatter::frontmatter::split(&content);
+        let frontmatter = match raw_frontmatter {
+            Some((format, raw)) => frontmatter::frontmatter::parse(&format, raw, path)?,
+            None => WingTemplateFrontmatter {
+                ..Default::default()
+            },
+        };
+
+        let output = output_location(path, &frontmatter, config).map_err(|e| {
+            WingError::io(
+                path,
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+            )
+        })?;
+
+        let mut created: DateTime<Utc> = DateTime::<Utc>::from(SystemTime::now());
+        let mut modified: DateTime<Utc> = DateTime::<Utc>::from(SystemTime::now());
+        if let Ok(meta) = fs::metadata(path) {
+            // not every filesystem has creation times, and the current time would change the cache's site hash on every build
+            let modified_time = meta.modified().unwrap_or(SystemTime::now());
+            created = DateTime::<Utc>::from(meta.created().unwrap_or(modified_time));
+            modified = DateTime::<Utc>::from(modified_time);
+        }
+
+        let word_count = Parser::new(markdown)
+            .map(|event| match event {
+                Event::Text(text) => text.split_whitespace().count(),
+                _ => 0,
+            })
+            .sum();
+
+        let is_section = path.file_name().map_or(false, |n| n == SECTION_INDEX);
+        let name = if is_section == true {
+            item_name(path.parent().unwrap_or(Path::new("")), config)
+        } else {
+            item_name(path, config)
+        };
+        let site_location = links::links::site_segments(&output, config);
+        let summary = WingPageSummary {
+            title: frontmatter
+                .title
+                .clone()
+                .unwrap_or(sections::sections::default_title(&name)),
+            section: sections::sections::parent_path(&name).unwrap_or_default(),
+            path: name,
+            link: links::links::page_path(&site_location, config),
+            url: links::links::page_url(&site_location, config),
+            frontmatter: frontmatter.clone(),
+            created: created.format("%Y-%m-%d %H:%M").to_string(),
+            modified: modified.format("%Y-%m-%d %H:%M").to_string(),
+            word_count,
+        };
+
+        let template = if frontmatter.template.len() > 0 {
+            frontmatter.template.clone()
+        } else if is_section == true {
+            String::from("section")
+        } else {
+            String::from("index")
+        };
+
+        Ok(WingSource {
+            path: path.to_path_buf(),
+            section: if is_section == true {
+                Some(summary.path.clone())
+            } else {
+                None
+            },
+            template,
+            content,
+            frontmatter,
+            output,
+            created,
+            modified,
+            summary,
+        })
+    }
+}
+
+impl WingSource {
+    /// Reads a section's `_index.md`, or stands in for it if the section doesn't have one
+    pub fn section(path: &str, config: &WingConfig) -> std::result::Result<WingSource, WingError> {
+        let dir = sections::sections::section_dir(path, config);
+        let index = dir.join(SECTION_INDEX);
+        if index.is_file() == true {
+            return WingSource::read(&index, config);
+        }
+
+        // the directory's time is used, so the page is only re-rendered when the section changes
+        let modified = DateTime::<Utc>::from(
+            fs::metadata(&dir)
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        );
+        let output = output_location(
+            &index,
+            &WingTemplateFrontmatter {
+                ..Default::default()
+            },
+            config,
+        )
+        .map_err(|e| {
+            WingError::io(
+                &index,
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+            )
+        })?;
+        let site_location = links::links::site_segments(&output, config);
+
+        Ok(WingSource {
+            path: index,
+            section: Some(String::from(path)),
+            template: String::from("section"),
+            content: String::new(),
+            frontmatter: WingTemplateFrontmatter {
+                ..Default::default()
+            },
+            output,
+            created: modified,
+            modified,
+            summary: WingPageSummary {
+                path: String::from(path),
+                link: links::links::page_path(&site_location, config),
+                url: links::links::page_url(&site_location, config),
+                title: sections::sections::default_title(path),
+                frontmatter: WingTemplateFrontmatter {
+                    ..Default::default()
+                },
+                created: modified.format("%Y-%m-%d %H:%M").to_string(),
+                modified: modified.format("%Y-%m-%d %H:%M").to_string(),
+                word_count: 0,
+                section: sections::sections::parent_path(path).unwrap_or_default(),
+            },
+        })
+    }
 }
 
 /// Represents a template
@@ -102,175 +536,609 @@ pub struct WingTemplate {
     pub content: String,
     /// Path to raw MarkDown
     pub content_path: String,
-    /// Completed content (content + template)
+    /// Completed content (content + template).  Empty if the page wasn't rendered.
     pub completed: String,
     /// Path to completed file
     pub completed_file: String,
+    /// frontmatter
+    pub frontmatter: WingTemplateFrontmatter,
+    /// Time file was created
+    pub created: DateTime<Utc>,
+    /// Last time file was modified
+    pub modified: DateTime<Utc>,
+    /// Bytes removed from the completed file by optimisation
+    pub bytes_saved: usize,
+    /// If `false`, the page was unchanged since the last build, so wasn't rendered
+    pub rendered: bool,
+    /// Inputs used to render the page, for the build cache
+    pub cache_entry: CachedPage,
+    /// For a section's listing page, the section's path relative to the content directory
+    pub section: Option<String>,
+    /// For a paginated section's listing page, paths to the completed files of its later pages
+    pub pagers: Vec<String>,
+    /// Summary of the page, as given to templates
+    pub summary: WingPageSummary,
 }
 
 impl WingTemplate {
     pub fn new(
         tera: &Tera,
-        content: &Path,
-        _config: &WingConfig,
-        index: &Vec<String>,
-    ) -> std::result::Result<(), std::io::Error> {
-        let content_file_path = format!(
-            r"{}\{}",
-            std::env::current_dir()
-                .expect("Cannot get current working directory.")
-                .display(),
-            content.display()
+        source: &WingSource,
+        config: &WingConfig,
+        site: &WingSite,
+        assets: &HashMap<String, String>,
+        cache: &BuildCache,
+    ) -> std::result::Result<WingTemplate, WingError> {
+        let content = source.path.as_path();
+        let content_data = source.content.clone();
+        let (_frontmatter, markdown) = frontmatter::frontmatter::split(&source.content);
+        let frontmatter = source.frontmatter.clone();
+        let completed_file_location = source.output.clone();
+        let created = source.created;
+        let modified = source.modified;
+
+        let template_file = format!("{}.html", source.template);
+
+        // the page is re-rendered if its template, or the template of a shortcode it uses, changes
+        let mut templates = vec![template_file.clone()];
+        templates.extend(
+            shortcodes::shortcodes::names(markdown)
+                .into_iter()
+                .map(|name| format!("shortcodes/{}.html", name)),
         );
 
-        let content_file = Path::new(&content_file_path);
+        let section = match &source.section {
+            Some(path) => site.sections.get(path),
+            None => site.sections.get(&source.summary.section),
+        };
+        let ancestors = sections::sections::ancestors(&source.summary.path, &site.sections);
 
-        let content_data = match fs::read_to_string(content_file) {
-            Ok(s) => s,
-            Err(e) => {
-                log(
-                    &format!(
-                        "Failed to read content of {}: {}",
-                        content_file.display(),
-                        e
-                    ),
-                    "f",
-                )
-                .unwrap();
-                std::process::exit(1)
-            }
+        // other pages only need re-rendering (and adding to the context) if the template shows them
+        let site_data = cache.site_data(&template_file);
+        let site_hash = match site_data {
+            SiteData::All => cache.site.clone(),
+            SiteData::Section => cache::cache::hash(
+                &serde_json::to_string(&serde_json::json!([section, ancestors]))
+                    .unwrap_or_default(),
+            ),
+            SiteData::None => String::new(),
         };
 
-        let with_replaced_extension = content.with_extension("html");
+        let cache_entry = CachedPage {
+            content: cache::cache::hash(&format!("{}\n{}", modified, content_data)),
+            template: templates
+                .iter()
+                .map(|t| cache.templates.get(t).cloned().unwrap_or_default())
+                .collect::<Vec<String>>()
+                .join(","),
+            site: site_hash,
+            output: completed_file_location.display().to_string(),
+        };
 
-        let completed_file_location =
-            Path::new("site\\").join(with_replaced_extension.strip_prefix("content/").expect(
-                &format!(
-                    "Failed to generate new content location.  Computed content location: {}",
-                    with_replaced_extension.display()
-                ),
-            ));
+        let site_location = links::links::site_segments(&completed_file_location, config);
 
-        let parent = completed_file_location.parent().unwrap();
-        if parent.is_dir() == false {
-            fs::create_dir_all(parent)?;
+        // a section's listing page can be split into several pages, the first being the listing page itself
+        let pagers = match source.section.as_ref().and_then(|p| site.sections.get(p)) {
+            Some(section) => pagination::pagination::paginate(
+                &section.pages,
+                frontmatter.paginate_by.unwrap_or(config.paginate_by),
+                &site_location,
+                config,
+            ),
+            None => vec![],
+        };
+        let pager_files: Vec<PathBuf> = pagers
+            .iter()
+            .skip(1)
+            .map(|(location, _paginator)| {
+                location
+                    .iter()
+                    .fold(Path::new(&config.output_dir).to_path_buf(), |l, s| {
+                        l.join(s)
+                    })
+            })
+            .collect();
+
+        if cache.is_fresh(&content.display().to_string(), &cache_entry, &pager_files) {
+            return Ok(WingTemplate {
+                content: content_data,
+                content_path: content.display().to_string(),
+                completed: String::new(),
+                completed_file: completed_file_location.display().to_string(),
+                frontmatter,
+                created,
+                modified,
+                bytes_saved: 0,
+                rendered: false,
+                cache_entry,
+                section: source.section.clone(),
+                pagers: pager_files
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect(),
+                summary: source.summary.clone(),
+            });
         }
 
-        let mut frontmatter = WingTemplateFrontmatter {
-            template: String::new(),
+        if let Some(parent) = completed_file_location.parent() {
+            if parent.is_dir() == false {
+                fs::create_dir_all(parent).map_err(|e| WingError::io(parent, e))?;
+            }
+        }
+
+        let source_location = links::links::source_segments(content, config);
+        let not_found_config = not_found_links(config);
+        let link_config = if is_not_found(&completed_file_location, config) == true {
+            &not_found_config
+        } else {
+            config
         };
 
         let mut options = Options::empty();
         options.insert(Options::all());
-        let parser = Parser::new_ext(content_data.as_str(), options).map(|event| {
-            if let Event::Text(text) = event.clone() {
-                if text.starts_with("template: ") && frontmatter.template.len() == 0 {
-                    let raw_frontmatter: WingTemplateFrontmatter =
-                        serde_yaml::from_str(text.to_string().as_str())
-                            .expect("Couldn't read file frontmatter.");
-                    frontmatter.template = raw_frontmatter.template;
-
-                    Event::Html(CowStr::Borrowed(""))
-                } else {
-                    event
+
+        let mut breadcrumbs = ancestors.clone();
+        breadcrumbs.push(source.summary.to_link());
+
+        // renders the page to `location`, which only differs from `site_location` for later pages of a listing
+        let render = |location: &Vec<String>,
+                      paginator: Option<WingPaginator>|
+         -> std::result::Result<String, WingError> {
+            let (markdown, expanded) = shortcodes::shortcodes::expand(
+                markdown,
+                tera,
+                location,
+                &source_location,
+                link_config,
+            )
+            .map_err(|e| WingError::template(Some(content), &e))?;
+            let parser = Parser::new_ext(&markdown, options).map(|event| match event {
+                // autolinked email addresses get `mailto:` added when rendered, so aren't paths
+                Event::Start(Tag::Link(link_type, destination, title))
+                    if link_type != LinkType::Email =>
+                {
+                    Event::Start(Tag::Link(
+                        link_type,
+                        CowStr::from(links::links::rewrite(
+                            &destination,
+                            location,
+                            &source_location,
+                            link_config,
+                        )),
+                        title,
+                    ))
+                }
+                Event::Start(Tag::Image(link_type, destination, title)) => {
+                    Event::Start(Tag::Image(
+                        link_type,
+                        CowStr::from(links::links::rewrite(
+                            &destination,
+                            location,
+                            &source_location,
+                            link_config,
+                        )),
+                        title,
+                    ))
                 }
+                _ => event,
+            });
+
+            let (events, toc) = toc::toc::headings(parser, config.heading_anchors);
+            let mut html_output = String::new();
+            if config.highlight.enabled == true {
+                html::push_html(
+                    &mut html_output,
+                    highlight::highlight::code_blocks(events.into_iter(), &config.highlight)
+                        .into_iter(),
+                );
             } else {
-                event
+                html::push_html(&mut html_output, events.into_iter());
             }
-        });
+            let html_output = shortcodes::shortcodes::substitute(&html_output, &expanded);
 
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
+            let ctx = &WingTemplateData {
+                content: html_output,
+                toc,
+                items: Some(&site.pages).filter(|_| site_data == SiteData::All),
+                current: &source.summary,
+                section: section.filter(|_| site_data != SiteData::None),
+                parent: ancestors.last().cloned(),
+                ancestors: ancestors.clone(),
+                breadcrumbs: breadcrumbs.clone(),
+                taxonomies: Some(&site.taxonomies).filter(|_| site_data == SiteData::All),
+                paginator,
+                frontmatter: frontmatter.clone(),
+                created: created.format("%Y-%m-%d %H:%M").to_string(),
+                modified: modified.format("%Y-%m-%d %H:%M").to_string(),
+                url: links::links::page_url(location, config),
+            };
 
-        let mut created: DateTime<Utc> = DateTime::<Utc>::from(SystemTime::now());
-        let mut modified: DateTime<Utc> = DateTime::<Utc>::from(SystemTime::now());
-        if let Ok(meta) = fs::metadata(content) {
-            created = DateTime::<Utc>::from(meta.created().unwrap_or(SystemTime::now()));
-            modified = DateTime::<Utc>::from(meta.modified().unwrap_or(SystemTime::now()));
+            let context =
+                Context::from_serialize(ctx).map_err(|e| WingError::template(Some(content), &e))?;
+            match tera.render(template_file.as_str(), &context) {
+                Ok(s) => Ok(links::links::rewrite_html(
+                    &optimise::optimise::fingerprint_references(&s, assets),
+                    location,
+                    // links from the MarkDown have already been rewritten, so this only affects the template's
+                    location,
+                    link_config,
+                )),
+                Err(e) => Err(WingError::template(Some(content), &e)),
+            }
+        };
+
+        let mut pagers = pagers.into_iter();
+        let rendered = render(&site_location, pagers.next().map(|(_l, p)| p))?;
+        for ((location, paginator), pager_file) in pagers.zip(pager_files.iter()) {
+            let pager = minify_page(render(&location, Some(paginator))?, config);
+            if let Some(parent) = pager_file.parent() {
+                fs::create_dir_all(parent).map_err(|e| WingError::io(parent, e))?;
+            }
+            write_if_changed(pager_file, pager.as_bytes())
+                .map_err(|e| WingError::io(pager_file, e))?;
         }
 
-        let ctx = &WingTemplateData {
-            content: html_output,
-            items: index.clone(),
-            current: content
-                .display()
-                .to_string()
-                .replacen("content\\", "", 1)
-                .replacen(".md", "", 1),
-            frontmatter: frontmatter.clone(),
-            created: created.format("%Y-%m-%d %H:%M").to_string(),
-            modified: modified.format("%Y-%m-%d %H:%M").to_string(),
-        };
+        let rendered_len = rendered.len();
+        let completed = minify_page(rendered, config);
+        let bytes_saved = rendered_len - completed.len();
 
-        let template = if frontmatter.template.len() == 0 {
-            String::from("index")
-        } else {
-            frontmatter.template
-        };
+        match write_if_changed(&completed_file_location, completed.as_bytes()) {
+            Ok(_written) => Ok(WingTemplate {
+                content: content_data,
+                content_path: content.display().to_string(),
+                completed,
+                completed_file: completed_file_location.display().to_string(),
+                frontmatter,
+                created,
+                modified,
+                bytes_saved,
+                rendered: true,
+                cache_entry,
+                section: source.section.clone(),
+                pagers: pager_files
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect(),
+                summary: source.summary.clone(),
+            }),
 
-        let completed = match tera.render(
-            format!("{}.html", template).as_str(),
-            &Context::from_serialize(ctx).expect("Failed to write template"),
-        ) {
-            Ok(s) => s,
-            Err(e) => {
-                log(&format!("Failed to render template: {}", e), "f").unwrap();
-                std::process::exit(1);
-            }
-        };
+            Err(e) => Err(WingError::io(&completed_file_location, e)),
+        }
+    }
+}
+
+/// Writes `contents` to `path` in a single write, unless the file already contains exactly `contents`.  Unchanged files are left alone, so their modification times stay the same.
+///
+/// Returns `true` if the file was written.
+pub fn write_if_changed(path: &Path, contents: &[u8]) -> std::io::Result<bool> {
+    if let Ok(meta) = fs::metadata(path) {
+        if meta.is_file() && meta.len() == contents.len() as u64 && fs::read(path)? == contents {
+            return Ok(false);
+        }
+    }
+
+    fs::write(path, contents)?;
+    Ok(true)
+}
+
+/// Gets the name of a content file, relative to the content directory and without its extension, e.g. `content/blog/post.md` becomes `blog/post`
+pub fn item_name(path: &Path, config: &WingConfig) -> String {
+    let relative = path.strip_prefix(&config.content_dir).unwrap_or(path);
+    let segments: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    segments.join("/")
+}
+
+/// Gets the location a content file is built to, e.g. `content/blog/post.md` becomes `site/blog/post.html`, or `site/blog/post/index.html` with `directory` URLs.  Index pages and the 404 page are always built to `.html` files.
+pub fn output_location(
+    path: &Path,
+    frontmatter: &WingTemplateFrontmatter,
+    config: &WingConfig,
+) -> std::result::Result<PathBuf, std::path::StripPrefixError> {
+    let relative = path.strip_prefix(&config.content_dir)?;
+    if relative.file_name().map_or(false, |n| n == SECTION_INDEX) {
+        return Ok(Path::new(&config.output_dir)
+            .join(relative.parent().unwrap_or(Path::new("")))
+            .join("index.html"));
+    }
+
+    let name = match &frontmatter.slug {
+        Some(slug) => slug.clone(),
+        None => relative
+            .file_stem()
+            .map_or(String::new(), |s| s.to_string_lossy().to_string()),
+    };
+    let dir = Path::new(&config.output_dir).join(relative.parent().unwrap_or(Path::new("")));
 
-        let completed_bytes = completed.as_bytes();
+    let is_not_found = dir == Path::new(&config.output_dir) && name == "404";
+    if config.url_style == "directory" && name != "index" && is_not_found == false {
+        Ok(dir.join(name).join("index.html"))
+    } else {
+        Ok(dir.join(format!("{}.html", name)))
+    }
+}
 
-        let file = fs::File::create(completed_file_location.to_owned())?;
+/// Location of the page served for missing URLs, in the output directory
+pub static NOT_FOUND_PAGE: &'static str = "404.html";
 
-        let mut stream = BufWriter::new(file);
+/// Checks if an output file is the site's 404 page
+pub fn is_not_found(completed_file: &Path, config: &WingConfig) -> bool {
+    links::links::site_segments(completed_file, config) == [NOT_FOUND_PAGE]
+}
 
-        for i in 0..completed.len() {
-            stream.write(&[completed_bytes[i]]).unwrap();
+/// The 404 page is served in place of missing pages at any depth, so its links are kept root-relative
+fn not_found_links(config: &WingConfig) -> WingConfig {
+    WingConfig {
+        link_type: String::from("absolute"),
+        base_url: String::new(),
+        ..config.clone()
+    }
+}
+
+/// Minifies a rendered page according to `optimisation_level`
+fn minify_page(rendered: String, config: &WingConfig) -> String {
+    match config.optimisation_level.as_str() {
+        "low" => optimise::optimise::minify_html(&rendered),
+        "high" => {
+            optimise::optimise::minify_html(&optimise::optimise::strip_unused_attributes(&rendered))
         }
+        _ => rendered,
+    }
+}
 
-        match stream.flush() {
-            Ok(()) => Ok(()),
+/// Renders `notFoundTemplate` to `404.html`, for sites without a `404.md`, returning its location
+fn generate_not_found(
+    tera: &Tera,
+    config: &WingConfig,
+    site: &WingSite,
+    assets: &HashMap<String, String>,
+) -> std::result::Result<PathBuf, WingError> {
+    let template_file = format!("{}.html", config.not_found_template);
+    let location = Path::new(&config.output_dir).join(NOT_FOUND_PAGE);
+    let now = DateTime::<Utc>::from(SystemTime::now())
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
 
-            Err(e) => {
-                log(
-                    &format!(
-                        "Failed to write completed file in {}.  Error: {}",
-                        completed_file_location.to_str().unwrap(),
-                        e
+    let site_location = [String::from(NOT_FOUND_PAGE)];
+    let current = WingPageSummary {
+        path: String::from("404"),
+        link: links::links::page_path(&site_location, config),
+        url: links::links::page_url(&site_location, config),
+        title: String::from("404"),
+        frontmatter: WingTemplateFrontmatter {
+            ..Default::default()
+        },
+        created: now.clone(),
+        modified: now.clone(),
+        word_count: 0,
+        section: String::new(),
+    };
+
+    let ctx = &WingTemplateData {
+        content: String::new(),
+        toc: vec![],
+        items: Some(&site.pages),
+        current: &current,
+        section: None,
+        parent: None,
+        ancestors: vec![],
+        breadcrumbs: vec![current.to_link()],
+        taxonomies: Some(&site.taxonomies),
+        paginator: None,
+        frontmatter: current.frontmatter.clone(),
+        created: now.clone(),
+        modified: now,
+        url: current.url.clone(),
+    };
+
+    let context = Context::from_serialize(ctx).map_err(|e| WingError::template(None, &e))?;
+    let rendered = tera
+        .render(template_file.as_str(), &context)
+        .map_err(|e| WingError::template(None, &e))?;
+    write_page(&rendered, &location, &not_found_links(config), assets)?;
+    Ok(location)
+}
+
+/// Finishes a page rendered outside of the content directory (fingerprinting assets, rewriting links and minifying) and writes it to `location`
+fn write_page(
+    rendered: &str,
+    location: &Path,
+    config: &WingConfig,
+    assets: &HashMap<String, String>,
+) -> std::result::Result<(), WingError> {
+    let site_location = links::links::site_segments(location, config);
+    let completed = minify_page(
+        links::links::rewrite_html(
+            &optimise::optimise::fingerprint_references(rendered, assets),
+            &site_location,
+            &site_location,
+            config,
+        ),
+        config,
+    );
+
+    if let Some(parent) = location.parent() {
+        fs::create_dir_all(parent).map_err(|e| WingError::io(parent, e))?;
+    }
+    write_if_changed(location, completed.as_bytes()).map_err(|e| WingError::io(location, e))?;
+    Ok(())
+}
+
+/// Gets where the redirect page for an alias, e.g. `/old/post`, is written
+fn alias_location(alias: &str, config: &WingConfig) -> PathBuf {
+    let alias = alias.trim_start_matches('/');
+    let output = Path::new(&config.output_dir);
+    if alias.len() == 0 || alias.ends_with('/') {
+        output.join(alias).join("index.html")
+    } else if alias.ends_with(".html") {
+        output.join(alias)
+    } else if config.url_style == "directory" {
+        output.join(alias).join("index.html")
+    } else {
+        output.join(format!("{}.html", alias))
+    }
+}
+
+/// Checks if a file in the output directory is a taxonomy term's feed, e.g. `tags/rust/rss.xml`
+fn is_term_feed(segments: &Vec<String>, config: &WingConfig) -> bool {
+    match segments.as_slice() {
+        [taxonomy, _term, file] => {
+            file == "rss.xml" && config.taxonomies.iter().any(|t| &t.name == taxonomy)
+        }
+        _ => false,
+    }
+}
+
+/// Checks that no two pages are built to the same place, and that aliases don't replace pages or each other
+fn check_outputs(
+    sources: &Vec<&WingSource>,
+    config: &WingConfig,
+) -> std::result::Result<(), WingError> {
+    let collision = |source: &WingSource, message: String| WingError::Frontmatter {
+        path: source.path.clone(),
+        line: None,
+        message,
+    };
+
+    let mut pages: HashMap<&PathBuf, &WingSource> = HashMap::new();
+    for source in sources.iter() {
+        if let Some(other) = pages.insert(&source.output, source) {
+            return Err(collision(
+                source,
+                format!(
+                    "would be built to {}, as is {}",
+                    source.output.display(),
+                    other.path.display()
+                ),
+            ));
+        }
+    }
+
+    let mut aliases: HashMap<PathBuf, &WingSource> = HashMap::new();
+    for source in sources.iter() {
+        for alias in source.frontmatter.aliases.iter() {
+            let location = alias_location(alias, config);
+            if let Some(other) = pages.get(&location) {
+                return Err(collision(
+                    source,
+                    format!(
+                        "alias \"{}\" would replace the page built from {}",
+                        alias,
+                        other.path.display()
                     ),
-                    "f",
-                )
-                .unwrap();
-                std::process::exit(1);
+                ));
+            }
+            if let Some(other) = aliases.insert(location, source) {
+                return Err(collision(
+                    source,
+                    format!(
+                        "alias \"{}\" is also an alias of {}",
+                        alias,
+                        other.path.display()
+                    ),
+                ));
             }
         }
     }
+
+    Ok(())
 }
 
-/// Builds a site
-// todo: return Result, split into smaller functions, tests
-pub fn build(app: Option<&clap::ArgMatches>, total_timing: Option<std::time::Instant>) {
-    if Path::new("./site/").is_dir() == true && app.is_some() == true {
-        if app
-            .unwrap()
-            .subcommand_matches("build")
-            .unwrap()
-            .is_present("force")
-            == false
-        {
-            log(
-                &String::from("Existing site found, run with -f to force."),
-                "f",
-            )
-            .unwrap();
-            return;
+/// Writes a redirect page for each of a page's `aliases`, returning their locations
+fn generate_aliases(page: &WingTemplate, config: &WingConfig) -> std::io::Result<Vec<PathBuf>> {
+    let target = links::links::page_path(
+        &links::links::site_segments(Path::new(&page.completed_file), config),
+        config,
+    );
+
+    let mut locations = Vec::new();
+    for alias in page.frontmatter.aliases.iter() {
+        let location = alias_location(alias, config);
+        let alias_location = links::links::site_segments(&location, config);
+        let url = links::links::rewrite(&target, &alias_location, &alias_location, config);
+
+        if let Some(parent) = location.parent() {
+            fs::create_dir_all(parent)?;
         }
+        write_if_changed(
+            &location,
+            format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<link rel=\"canonical\" href=\"{0}\">\n<meta http-equiv=\"refresh\" content=\"0; url={0}\">\n</head>\n<body><a href=\"{0}\">{0}</a></body>\n</html>\n",
+                url
+            )
+            .as_bytes(),
+        )?;
+        locations.push(location);
     }
 
+    Ok(locations)
+}
+
+lazy_static! {
+    /// Templates, reloaded from the template directory at the start of each build
+    pub static ref TERA_TEMPLATES: RwLock<Tera> = RwLock::new(Tera::default());
+}
+
+/// Summary of a completed build
+#[derive(Debug)]
+pub struct BuildReport {
+    /// Number of pages rendered
+    pub pages: usize,
+    /// Number of pages skipped, as they were unchanged since the last build
+    pub unchanged: usize,
+    /// Bytes saved by optimisation
+    pub bytes_saved: usize,
+    /// Time taken to build
+    pub elapsed: std::time::Duration,
+}
+
+/// Runs pre- or post-build scripts
+fn run_scripts(scripts: &Vec<String>) -> std::result::Result<(), WingError> {
+    for script in scripts.iter() {
+        let args: Vec<&str> = script.split("--").collect();
+        match Command::new(script)
+            .args(args)
+            .stdout(Stdio::piped())
+            .output()
+        {
+            Ok(v) => {
+                if v.status.success() == false {
+                    return Err(WingError::Script {
+                        script: script.clone(),
+                        message: format!(
+                            "exited with {}: {}",
+                            v.status,
+                            String::from_utf8_lossy(&v.stderr)
+                        ),
+                    });
+                }
+            }
+            Err(e) => {
+                return Err(WingError::Script {
+                    script: script.clone(),
+                    message: e.to_string(),
+                })
+            }
+        };
+    }
+
+    Ok(())
+}
+
+/// Builds a site.  Drafts, and pages outside their `publishDate` and `expiryDate`, are only built if `drafts` is `true`.
+pub fn build(
+    app: Option<&clap::ArgMatches>,
+    total_timing: Option<std::time::Instant>,
+    drafts: bool,
+) -> std::result::Result<BuildReport, WingError> {
+    let build_timing = std::time::Instant::now();
+
     let wing_config = match WingConfig::new() {
         Ok(val) => val,
-        Err(e) => {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
             log(
                 &format!("Using defaults for Wing config.  Error: {}", e),
                 "f",
@@ -280,92 +1148,233 @@ pub fn build(app: Option<&clap::ArgMatches>, total_timing: Option<std::time::Ins
                 ..Default::default()
             }
         }
+        Err(e) => return Err(WingError::Config(e.to_string())),
     };
 
-    for script in wing_config.pre_scripts.iter() {
-        let args: Vec<&str> = script.split("--").collect();
-        match Command::new(script)
-            .args(args)
-            .stdout(Stdio::piped())
-            .output()
-        {
-            Ok(_v) => continue,
-            Err(e) => {
-                log(
-                    &format!("Pre-run script failed with error: \"{}\"", e.to_string()),
-                    "f",
-                )
-                .unwrap();
-                std::process::exit(1)
-            }
-        };
+    // feeds and sitemaps need absolute links
+    if wing_config.rss == true && wing_config.base_url.len() == 0 {
+        return Err(WingError::Config(String::from(
+            "baseUrl must be set when rss is enabled",
+        )));
+    }
+    if wing_config.site_map == true && wing_config.base_url.len() == 0 {
+        return Err(WingError::Config(String::from(
+            "baseUrl must be set when siteMap is enabled",
+        )));
+    }
+    if let Some(taxonomy) = wing_config.taxonomies.iter().find(|t| t.feed == true) {
+        if wing_config.base_url.len() == 0 {
+            return Err(WingError::Config(format!(
+                "baseUrl must be set when the {} taxonomy has feeds",
+                taxonomy.name
+            )));
+        }
     }
 
+    let content_dir = Path::new(&wing_config.content_dir);
+    let output_dir = Path::new(&wing_config.output_dir);
+
     let mut previous_build_exists: bool = false;
-    if Path::new("./site/").is_dir() == true && app.is_some() == true {
+    if output_dir.is_dir() == true && app.is_some() == true {
         if app
             .unwrap()
             .subcommand_matches("build")
-            .unwrap()
-            .is_present("force")
+            .map_or(false, |b| b.is_present("force"))
             == false
         {
-            log(
-                &String::from("Previous builds already exist, run with -f to overwrite."),
-                "f",
-            )
-            .unwrap();
-            std::process::exit(1);
+            return Err(WingError::ExistingSite);
         }
 
         previous_build_exists = true;
     }
 
-    if Path::new("./site/").is_dir() == false {
-        fs::create_dir(Path::new(&format!("./site/"))).unwrap();
+    run_scripts(&wing_config.pre_scripts)?;
+
+    if output_dir.is_dir() == false {
+        fs::create_dir_all(output_dir).map_err(|e| WingError::io(output_dir, e))?;
     }
 
-    lazy_static! {
-        pub static ref TERA_TEMPLATES: Tera = {
-            let mut tera = match Tera::new("templates/**/*") {
-                Ok(t) => t,
-                Err(error) => {
-                    log(&format!("Failed to parse template(s): {}", error), "f").unwrap();
-                    std::process::exit(1);
-                }
-            };
-            tera.autoescape_on(vec![]);
-            tera
-        };
+    let mut templates = Tera::new(&format!("{}/**/*", wing_config.template_dir))
+        .map_err(|e| WingError::template(None, &e))?;
+    templates.autoescape_on(vec![]);
+    *TERA_TEMPLATES.write().unwrap() = templates;
+    if wing_config.highlight.enabled == true {
+        highlight::highlight::load_theme(&wing_config.highlight)?;
+    }
+    let tera = &*TERA_TEMPLATES.read().unwrap();
+
+    let mut report = optimise::optimise::OptimisationReport {
+        ..Default::default()
+    };
+    let assets = if wing_config.optimisation_level == "high" {
+        optimise::optimise::optimise_static(&wing_config, &mut report)
+            .map_err(|e| WingError::io(Path::new(&wing_config.static_dir), e))?
+    } else {
+        HashMap::new()
     };
 
-    let mut file_index = Vec::new();
-    for entry in WalkDir::new("content").min_depth(1) {
-        let file = entry.expect("Failed to read file.");
-        let path = file.path();
-        if path.is_file() == true && path.extension().unwrap() == "md" {
-            file_index.push(
-                String::from(file.path().to_str().unwrap())
-                    .replace("content\\", "")
-                    .replace(".md", ""),
-            );
+    let mut content_files = Vec::new();
+    let mut section_dirs = vec![String::new()];
+    for entry in WalkDir::new(content_dir).min_depth(1) {
+        let file = entry.map_err(|e| WingError::io(content_dir, e.into()))?;
+        let path = file.into_path();
+        if path.is_dir() == true {
+            section_dirs.push(links::links::source_segments(&path, &wing_config).join("/"));
+        } else if path.extension().map_or(false, |e| e == "md")
+            && path.file_name().map_or(false, |n| n != SECTION_INDEX)
+        {
+            content_files.push(path);
         }
     }
 
-    let index: std::cell::RefCell<Vec<_>> =
-        std::cell::RefCell::new(WalkDir::new("content").min_depth(1).into_iter().collect());
+    let now = Utc::now();
+    let is_published = |s: &WingSource| {
+        drafts == true || frontmatter::frontmatter::is_published(&s.frontmatter, now)
+    };
+    let (section_sources, mut unpublished): (Vec<WingSource>, Vec<WingSource>) = section_dirs
+        .par_iter()
+        .map(|dir| WingSource::section(dir, &wing_config))
+        .collect::<std::result::Result<Vec<WingSource>, WingError>>()?
+        .into_iter()
+        .partition(is_published);
 
-    index.into_inner().into_par_iter().for_each(|entry| {
-        let path = entry.unwrap().into_path();
-        if path.is_file() == true && path.extension().unwrap() == "md" {
-            match WingTemplate::new(&TERA_TEMPLATES, &path, &wing_config, &file_index) {
-                Ok(_template) => {}
-                Err(e) => {
-                    log(&String::from(e.to_string()), "f").unwrap();
-                }
-            };
+    // everything in an unpublished section is unpublished too, except in the top-level section, which only loses its listing page
+    let hidden: Vec<String> = unpublished
+        .iter()
+        .filter_map(|s| s.section.clone())
+        .filter(|path| path.len() > 0)
+        .collect();
+    let is_hidden = |path: &str| {
+        hidden
+            .iter()
+            .any(|h| path == h || path.starts_with(&format!("{}/", h)))
+    };
+    let (section_sources, hidden_sections): (Vec<WingSource>, Vec<WingSource>) = section_sources
+        .into_iter()
+        .partition(|s| s.section.as_ref().map_or(true, |p| is_hidden(p) == false));
+    section_dirs.retain(|dir| is_hidden(dir) == false);
+
+    let (page_sources, unpublished_pages): (Vec<WingSource>, Vec<WingSource>) = content_files
+        .par_iter()
+        .map(|path| WingSource::read(path, &wing_config))
+        .collect::<std::result::Result<Vec<WingSource>, WingError>>()?
+        .into_iter()
+        .partition(|s| is_published(s) && is_hidden(&s.summary.section) == false);
+    unpublished.extend(hidden_sections);
+    unpublished.extend(unpublished_pages);
+    if unpublished.len() > 0 {
+        log(
+            &format!(
+                "skipped {} draft or scheduled page(s), use --drafts to include them",
+                unpublished.len()
+            ),
+            "c",
+        )
+        .unwrap();
+    }
+
+    let mut summaries: Vec<WingPageSummary> =
+        page_sources.iter().map(|s| s.summary.clone()).collect();
+    summaries.sort_by(|a, b| a.path.cmp(&b.path));
+    let site = WingSite {
+        sections: sections::sections::collect(
+            &section_dirs,
+            &summaries,
+            &section_sources.iter().collect(),
+            &wing_config,
+        ),
+        taxonomies: taxonomies::taxonomies::collect(&summaries, &wing_config)?,
+        pages: summaries,
+    };
+
+    // sections get a listing page if they have an `_index.md`, or there's a `section.html` template, unless a page is already built there (e.g. from an `index.md`)
+    let has_section_template = tera.get_template("section.html").is_ok();
+    let page_outputs: HashSet<&PathBuf> = page_sources.iter().map(|s| &s.output).collect();
+    let sources: Vec<&WingSource> = page_sources
+        .iter()
+        .chain(section_sources.iter().filter(|s| {
+            page_outputs.contains(&s.output) == false
+                && (s.path.is_file() == true || has_section_template == true)
+        }))
+        .collect();
+
+    check_outputs(&sources, &wing_config)?;
+
+    let mut cache = BuildCache::load();
+    cache.prepare(tera, &wing_config, &site, &assets);
+
+    let pages: Vec<WingTemplate> = sources
+        .par_iter()
+        .map(|source| WingTemplate::new(tera, source, &wing_config, &site, &assets, &cache))
+        .collect::<std::result::Result<Vec<WingTemplate>, WingError>>()?;
+
+    let mut outputs: HashSet<Vec<String>> = HashSet::new();
+    let static_files = assets::assets::copy_static(&wing_config, &assets)
+        .map_err(|e| WingError::io(Path::new(&wing_config.static_dir), e))?;
+    for file in static_files.iter() {
+        outputs.insert(links::links::site_segments(file, &wing_config));
+    }
+
+    for page in pages.iter() {
+        outputs.insert(links::links::site_segments(
+            Path::new(&page.completed_file),
+            &wing_config,
+        ));
+        for pager in page.pagers.iter() {
+            outputs.insert(links::links::site_segments(Path::new(pager), &wing_config));
+        }
+        let aliases = generate_aliases(page, &wing_config)
+            .map_err(|e| WingError::io(Path::new(&page.content_path), e))?;
+        for alias in aliases.iter() {
+            outputs.insert(links::links::site_segments(alias, &wing_config));
         }
-    });
+    }
+
+    let has_not_found = pages
+        .iter()
+        .any(|p| is_not_found(Path::new(&p.completed_file), &wing_config));
+    if has_not_found == false && wing_config.not_found_template.len() > 0 {
+        let location = generate_not_found(tera, &wing_config, &site, &assets)?;
+        outputs.insert(links::links::site_segments(&location, &wing_config));
+    }
+
+    let taxonomy_pages = taxonomies::taxonomies::generate(tera, &site, &wing_config, &assets)?;
+    for location in taxonomy_pages.iter() {
+        outputs.insert(links::links::site_segments(location, &wing_config));
+    }
+
+    if wing_config.optimisation_level == "low" || wing_config.optimisation_level == "high" {
+        for page in pages.iter() {
+            report.original += page.completed.len() + page.bytes_saved;
+            report.optimised += page.completed.len();
+        }
+
+        log(
+            &format!(
+                "optimising, saved {} of {} bytes",
+                report.saved(),
+                report.original
+            ),
+            "c",
+        )
+        .unwrap();
+    }
+
+    if wing_config.rss == true {
+        rss::rss::generate(&pages, &wing_config)
+            .map_err(|e| WingError::io(&output_dir.join("rss.xml"), e))?;
+    }
+
+    let feeds = taxonomies::taxonomies::generate_feeds(&pages, &site, &wing_config)
+        .map_err(|e| WingError::io(output_dir, e))?;
+    for location in feeds.iter() {
+        outputs.insert(links::links::site_segments(location, &wing_config));
+    }
+
+    if wing_config.site_map == true {
+        sitemap::sitemap::generate(&pages, &taxonomy_pages, &wing_config)
+            .map_err(|e| WingError::io(&output_dir.join("sitemap.xml"), e))?;
+    }
 
     if let Some(timing) = total_timing {
         log(
@@ -375,39 +1384,68 @@ pub fn build(app: Option<&clap::ArgMatches>, total_timing: Option<std::time::Ins
         .unwrap();
     }
 
-    if previous_build_exists == true {
-        for entry in WalkDir::new("site").min_depth(1) {
-            let file = entry.expect("Failed to read file.");
-            let path = file.path();
-            if path.is_file() == true && path.extension().unwrap() == "html" {
-                let path_normalised = String::from(file.path().to_str().unwrap())
-                    .replace("site\\", "")
-                    .replace(".html", "");
-                if file_index.contains(&path_normalised) == false {
-                    fs::remove_file(&path).unwrap();
+    // pages that were published by an earlier build, but aren't any more
+    for source in unpublished.iter() {
+        let mut stale = vec![source.output.clone()];
+        for alias in source.frontmatter.aliases.iter() {
+            stale.push(alias_location(alias, &wing_config));
+        }
+        // a section's listing page may have been paginated
+        if source.section.is_some() {
+            let first = links::links::site_segments(&source.output, &wing_config);
+            for number in 2.. {
+                let pager = pagination::pagination::pager_location(&first, number)
+                    .iter()
+                    .fold(output_dir.to_path_buf(), |l, s| l.join(s));
+                if pager.is_file() == false {
+                    break;
                 }
+                stale.push(pager);
+            }
+        }
+
+        for path in stale.iter() {
+            let segments = links::links::site_segments(path, &wing_config);
+            if path.is_file() == true && outputs.contains(&segments) == false {
+                fs::remove_file(path).map_err(|e| WingError::io(path, e))?;
             }
         }
     }
 
-    for script in wing_config.post_scripts.iter() {
-        let args: Vec<&str> = script.split("--").collect();
-        match Command::new(script)
-            .args(args)
-            .stdout(Stdio::piped())
-            .output()
-        {
-            Ok(_v) => continue,
-            Err(e) => {
-                log(
-                    &format!("Post-run script failed with error: \"{}\"", e.to_string()),
-                    "f",
-                )
-                .unwrap();
-                std::process::exit(1)
+    if previous_build_exists == true {
+        for entry in WalkDir::new(output_dir).min_depth(1) {
+            let file = entry.map_err(|e| WingError::io(output_dir, e.into()))?;
+            let path = file.path();
+            let segments = links::links::site_segments(path, &wing_config);
+            let generated = path.extension().map_or(false, |e| e == "html")
+                || segments.first().map_or(false, |s| s == "static")
+                || is_term_feed(&segments, &wing_config);
+            if path.is_file() == true && generated == true {
+                if outputs.contains(&segments) == false {
+                    fs::remove_file(&path).map_err(|e| WingError::io(path, e))?;
+                }
             }
-        };
+        }
     }
+
+    cache
+        .save(
+            pages
+                .iter()
+                .map(|p| (p.content_path.clone(), p.cache_entry.clone()))
+                .collect(),
+        )
+        .map_err(|e| WingError::io(Path::new(cache::cache::CACHE_FILE), e))?;
+
+    run_scripts(&wing_config.post_scripts)?;
+
+    let unchanged = pages.iter().filter(|p| p.rendered == false).count();
+    Ok(BuildReport {
+        pages: pages.len() - unchanged,
+        unchanged,
+        bytes_saved: report.saved(),
+        elapsed: build_timing.elapsed(),
+    })
 }
 
 pub fn log(message: &String, message_type: &str) -> Result<()> {
@@ -457,27 +1495,93 @@ pub fn log(message: &String, message_type: &str) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::WingConfig;
+    use super::{is_not_found, item_name, output_location, WingConfig, WingTemplateFrontmatter};
+    use std::path::Path;
+
+    #[test]
+    pub fn test_paths() {
+        let config = WingConfig {
+            ..Default::default()
+        };
+        let frontmatter = WingTemplateFrontmatter {
+            ..Default::default()
+        };
+
+        assert_eq!(item_name(Path::new("content/index.md"), &config), "index");
+        assert_eq!(
+            item_name(Path::new("content/blog/post.md"), &config),
+            "blog/post"
+        );
+        assert_eq!(
+            output_location(Path::new("content/blog/post.md"), &frontmatter, &config).unwrap(),
+            Path::new("site").join("blog").join("post.html")
+        );
+
+        let slugged = WingTemplateFrontmatter {
+            slug: Some(String::from("hello")),
+            ..Default::default()
+        };
+        assert_eq!(
+            output_location(Path::new("content/blog/post.md"), &slugged, &config).unwrap(),
+            Path::new("site").join("blog").join("hello.html")
+        );
+
+        let nested = WingConfig {
+            content_dir: String::from("docs/src"),
+            output_dir: String::from("docs/site"),
+            ..Default::default()
+        };
+        assert_eq!(item_name(Path::new("docs/src/a/b.md"), &nested), "a/b");
+        assert_eq!(
+            output_location(Path::new("docs/src/a/b.md"), &frontmatter, &nested).unwrap(),
+            Path::new("docs/site").join("a").join("b.html")
+        );
+
+        let pretty = WingConfig {
+            url_style: String::from("directory"),
+            ..Default::default()
+        };
+        assert_eq!(
+            output_location(Path::new("content/blog/post.md"), &frontmatter, &pretty).unwrap(),
+            Path::new("site")
+                .join("blog")
+                .join("post")
+                .join("index.html")
+        );
+        assert_eq!(
+            output_location(Path::new("content/blog/index.md"), &frontmatter, &pretty).unwrap(),
+            Path::new("site").join("blog").join("index.html")
+        );
+        assert_eq!(
+            output_location(Path::new("content/404.md"), &frontmatter, &pretty).unwrap(),
+            Path::new("site").join("404.html")
+        );
+
+        assert_eq!(
+            is_not_found(&Path::new("site").join("404.html"), &config),
+            true
+        );
+        assert_eq!(
+            is_not_found(&Path::new("site").join("a").join("404.html"), &config),
+            false
+        );
+    }
+
     #[test]
     pub fn test_config() {
-        let from_fn = WingConfig::new();
+        // an empty configuration file uses the defaults for everything
+        let from_str: WingConfig = serde_json::from_str("{}").unwrap();
+        let defaults = WingConfig {
+            ..Default::default()
+        };
 
-        match from_fn {
-            Ok(val) => {
-                let defaults = WingConfig {
-                    ..Default::default()
-                };
-
-                assert_eq!(defaults.rss, val.rss);
-                assert_eq!(defaults.site_map, val.site_map);
-                assert_eq!(defaults.link_type, val.link_type);
-                assert_eq!(defaults.optimisation_level, val.optimisation_level);
-                assert_eq!(defaults.templates, val.templates);
-                assert_eq!(defaults.content, val.content);
-            }
-            Err(e) => {
-                assert_eq!(true, false, "WingConfig::new() failed: {}", e);
-            }
-        }
+        assert_eq!(defaults.rss, from_str.rss);
+        assert_eq!(defaults.site_map, from_str.site_map);
+        assert_eq!(defaults.link_type, from_str.link_type);
+        assert_eq!(defaults.optimisation_level, from_str.optimisation_level);
+        assert_eq!(defaults.template_dir, from_str.template_dir);
+        assert_eq!(defaults.content_dir, from_str.content_dir);
+        assert_eq!(defaults.output_dir, from_str.output_dir);
+        assert_eq!(defaults.url_style, from_str.url_style);
     }
 }
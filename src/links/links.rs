@@ -0,0 +1,268 @@
+// std
+use std::path::Path;
+
+// local
+use crate::WingConfig;
+
+/// Splits a completed file's location into its segments under the output directory, e.g. `site/a/b.html` becomes `["a", "b.html"]`
+pub fn site_segments(completed_file: &Path, config: &WingConfig) -> Vec<String> {
+    completed_file
+        .strip_prefix(&config.output_dir)
+        .unwrap_or(completed_file)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Checks if a link points somewhere other than the site (`https://`, `mailto:`, `//`, etc.) or is a fragment
+fn is_external(link: &str) -> bool {
+    if link.len() == 0 || link.starts_with('#') || link.starts_with("//") {
+        return true;
+    }
+
+    match link.find(|c| c == ':' || c == '/' || c == '?' || c == '#') {
+        Some(i) => &link[i..i + 1] == ":",
+        None => false,
+    }
+}
+
+/// Splits a content file's location into its segments under the content directory, e.g. `content/a/b.md` becomes `["a", "b.md"]`
+pub fn source_segments(content: &Path, config: &WingConfig) -> Vec<String> {
+    content
+        .strip_prefix(&config.content_dir)
+        .unwrap_or(content)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Gets the path a page is found at on the site, e.g. `["a", "b.html"]` becomes `/a/b.html`.  With `directory` URLs, `index.html` is left off, so `["a", "b", "index.html"]` becomes `/a/b/`.
+pub fn page_path(page: &[String], config: &WingConfig) -> String {
+    match page.split_last() {
+        Some((file, dirs)) if config.url_style == "directory" && file == "index.html" => {
+            if dirs.len() == 0 {
+                String::from("/")
+            } else {
+                format!("/{}/", dirs.join("/"))
+            }
+        }
+        _ => format!("/{}", page.join("/")),
+    }
+}
+
+/// Gets the canonical URL of a page, using the configured base URL
+pub fn page_url(page: &[String], config: &WingConfig) -> String {
+    format!(
+        "{}{}",
+        config.base_url.trim_end_matches('/'),
+        page_path(page, config)
+    )
+}
+
+/// Turns text into something usable in a URL, e.g. `Rust & Web` becomes `rust-web`
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_alphanumeric() == true {
+            slug.push(c);
+        } else if slug.len() > 0 && slug.ends_with('-') == false {
+            slug.push('-');
+        }
+    }
+
+    String::from(slug.trim_end_matches('-'))
+}
+
+/// Points a link to a `.md` file at the page it's built into, e.g. `a/b.md` becomes `a/b.html`, or `a/b/` with `directory` URLs
+fn built_path(path: &str, config: &WingConfig) -> String {
+    if path.ends_with(".md") == false {
+        return String::from(path);
+    }
+
+    let stem = path.trim_end_matches(".md");
+    if config.url_style == "directory" {
+        match stem.rsplit('/').next() {
+            Some("index") if stem == "index" => String::from("./"),
+            Some("index") => String::from(stem.trim_end_matches("index")),
+            _ => format!("{}/", stem),
+        }
+    } else {
+        format!("{}.html", stem)
+    }
+}
+
+/// Rewrites a link according to `link_type`.  `page` is the output file's location under the output directory, as returned by [`site_segments`], and `source` is the location relative links were written against, as returned by [`source_segments`].  They only differ in depth with `directory` URLs, where `a/b.md` is built to `a/b/index.html`.
+///
+/// Links to `.md` files are pointed to the page they are built into.  With `absolute`, links are prefixed with `base_url`.  With `relative`, root-relative links are made relative to `page`.
+pub fn rewrite(link: &str, page: &[String], source: &[String], config: &WingConfig) -> String {
+    if is_external(link) {
+        return String::from(link);
+    }
+
+    let (path, suffix) = match link.find(|c| c == '?' || c == '#') {
+        Some(i) => link.split_at(i),
+        None => (link, ""),
+    };
+
+    let path = built_path(path, config);
+
+    let depth = if page.len() > 0 { page.len() - 1 } else { 0 };
+    let source_depth = if source.len() > 0 {
+        source.len() - 1
+    } else {
+        0
+    };
+
+    if config.link_type == "absolute" {
+        let mut segments: Vec<&str> = if path.starts_with('/') {
+            vec![]
+        } else {
+            source[..source_depth].iter().map(|s| s.as_str()).collect()
+        };
+
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                s => segments.push(s),
+            }
+        }
+
+        let trailing = if path.ends_with('/') && segments.len() > 0 {
+            "/"
+        } else {
+            ""
+        };
+
+        format!(
+            "{}/{}{}{}",
+            config.base_url.trim_end_matches('/'),
+            segments.join("/"),
+            trailing,
+            suffix
+        )
+    } else if path.starts_with('/') {
+        let relative = format!("{}{}", "../".repeat(depth), path.trim_start_matches('/'));
+        if relative.len() == 0 {
+            format!("./{}", suffix)
+        } else {
+            format!("{}{}", relative, suffix)
+        }
+    } else if path.len() == 0 {
+        String::from(suffix)
+    } else {
+        let up = "../".repeat(depth.saturating_sub(source_depth));
+        match path.as_str() {
+            "./" if up.len() > 0 => format!("{}{}", up, suffix),
+            _ => format!("{}{}{}", up, path, suffix),
+        }
+    }
+}
+
+/// Rewrites the `href` and `src` attributes of all elements in an HTML document, using [`rewrite`]
+pub fn rewrite_html(html: &str, page: &[String], source: &[String], config: &WingConfig) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    loop {
+        let next = ["href=", "src="]
+            .iter()
+            .filter_map(|attr| {
+                rest.match_indices(attr)
+                    .find(|(i, _)| *i > 0 && rest[..*i].ends_with(char::is_whitespace))
+                    .map(|(i, a)| (i, a.len()))
+            })
+            .min();
+
+        let (start, len) = match next {
+            Some(v) => v,
+            None => break,
+        };
+
+        let value_start = start + len;
+        let quote = match rest[value_start..].chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => {
+                output.push_str(&rest[..value_start]);
+                rest = &rest[value_start..];
+                continue;
+            }
+        };
+
+        let end = match rest[value_start + 1..].find(quote) {
+            Some(e) => value_start + 1 + e,
+            None => break,
+        };
+
+        output.push_str(&rest[..value_start + 1]);
+        output.push_str(&rewrite(&rest[value_start + 1..end], page, source, config));
+        rest = &rest[end..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{page_path, rewrite};
+    use crate::WingConfig;
+
+    #[test]
+    pub fn test_rewrite() {
+        let page = vec![String::from("blog"), String::from("post.html")];
+        let mut config = WingConfig {
+            base_url: String::from("https://example.com/"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            rewrite("/static/index.css", &page, &page, &config),
+            "../static/index.css"
+        );
+        assert_eq!(
+            rewrite("other.md#top", &page, &page, &config),
+            "other.html#top"
+        );
+        assert_eq!(
+            rewrite("https://example.org", &page, &page, &config),
+            "https://example.org"
+        );
+        assert_eq!(rewrite("#top", &page, &page, &config), "#top");
+
+        config.link_type = String::from("absolute");
+        assert_eq!(
+            rewrite("../index.md", &page, &page, &config),
+            "https://example.com/index.html"
+        );
+        assert_eq!(
+            rewrite("/static/index.css", &page, &page, &config),
+            "https://example.com/static/index.css"
+        );
+
+        let pretty = vec![
+            String::from("blog"),
+            String::from("post"),
+            String::from("index.html"),
+        ];
+        let source = vec![String::from("blog"), String::from("post.md")];
+        config.link_type = String::from("relative");
+        config.url_style = String::from("directory");
+        assert_eq!(rewrite("other.md", &pretty, &source, &config), "../other/");
+        assert_eq!(rewrite("index.md", &pretty, &source, &config), "../");
+        assert_eq!(
+            rewrite("/static/index.css", &pretty, &source, &config),
+            "../../static/index.css"
+        );
+        assert_eq!(page_path(&pretty, &config), "/blog/post/");
+        assert_eq!(page_path(&[String::from("index.html")], &config), "/");
+
+        config.link_type = String::from("absolute");
+        assert_eq!(
+            rewrite("other.md#top", &pretty, &source, &config),
+            "https://example.com/blog/other/#top"
+        );
+    }
+}
@@ -0,0 +1,2 @@
+/// Link rewriting, using the configured link type.
+pub mod links;
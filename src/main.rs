@@ -12,9 +12,9 @@ mod new;
 use new::new::generate_new;
 
 mod serve;
-use serve::serve::init;
+use serve::serve::{init, ServeOptions};
 
-use wsg::{build, log};
+use wsg::{build, highlight::highlight::theme_css, log, WingConfig};
 
 fn main() {
     let total_timing = Instant::now();
@@ -29,18 +29,37 @@ fn main() {
                 .version(env!("CARGO_PKG_VERSION"))
                 .author(env!("CARGO_PKG_AUTHORS"))
                 .arg(
+                    Arg::with_name("open")
+                        .long("open")
+                        .short("o")
+                        .help("Opens the site in the browser once it's being served."),
+                )
+                .arg(
+                    // the site used to be opened unless this was passed, so it's kept for existing scripts
                     Arg::with_name("silent")
                         .long("silent")
                         .short("s")
-                        .help("Prevents site being opened in the browser automatically."),
+                        .hidden(true),
+                )
+                .arg(
+                    Arg::with_name("host")
+                        .long("host")
+                        .help("The address to serve on, e.g. 0.0.0.0 to test on other devices.")
+                        .required(false)
+                        .takes_value(true),
                 )
                 .arg(
                     Arg::with_name("port")
                         .long("port")
                         .short("p")
-                        .help("The port to use.")
+                        .help("The port to use, or the first free one after it.")
                         .required(false)
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("drafts")
+                        .long("drafts")
+                        .help("Includes drafts and scheduled pages."),
                 ),
         )
         .subcommand(
@@ -52,6 +71,23 @@ fn main() {
                     Arg::with_name("force")
                         .short("f")
                         .help("Deletes existing site, if any."),
+                )
+                .arg(
+                    Arg::with_name("drafts")
+                        .long("drafts")
+                        .help("Includes drafts and scheduled pages."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("highlight-css")
+                .about("Prints the CSS for a highlighting theme.")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("theme")
+                        .long("theme")
+                        .help("The theme to use, instead of the configured one.")
+                        .takes_value(true),
                 ),
         )
         .subcommand(
@@ -65,15 +101,54 @@ fn main() {
                         .default_value("site")
                         .help("The name of the site you want to create")
                         .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("content-dir")
+                        .long("content-dir")
+                        .help("Directory for MarkDown content.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("template-dir")
+                        .long("template-dir")
+                        .help("Directory for templates.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output-dir")
+                        .long("output-dir")
+                        .help("Directory the site is built to.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("static-dir")
+                        .long("static-dir")
+                        .help("Directory for static content.")
+                        .takes_value(true),
                 ),
         )
         .get_matches();
 
-    if let Some(_v) = app.subcommand_matches("build") {
-        build(Some(&app), Some(total_timing));
+    if let Some(v) = app.subcommand_matches("build") {
+        if let Err(e) = build(Some(&app), Some(total_timing), v.is_present("drafts")) {
+            log(&e.to_string(), "f").unwrap();
+            std::process::exit(1);
+        }
     } else if let Some(v) = app.subcommand_matches("new") {
         log(&String::from("new project"), "g").unwrap();
-        match generate_new(v.value_of("name").unwrap()) {
+        let defaults = WingConfig {
+            ..Default::default()
+        };
+        let config = WingConfig {
+            content_dir: String::from(v.value_of("content-dir").unwrap_or(&defaults.content_dir)),
+            template_dir: String::from(
+                v.value_of("template-dir").unwrap_or(&defaults.template_dir),
+            ),
+            output_dir: String::from(v.value_of("output-dir").unwrap_or(&defaults.output_dir)),
+            static_dir: String::from(v.value_of("static-dir").unwrap_or(&defaults.static_dir)),
+            ..Default::default()
+        };
+        match generate_new(v.value_of("name").unwrap(), &config) {
             Ok(()) => {
                 log(
                     &format!("Created project {}!", v.value_of("name").unwrap()),
@@ -86,11 +161,30 @@ fn main() {
                 std::process::exit(1);
             }
         };
+    } else if let Some(v) = app.subcommand_matches("highlight-css") {
+        let config = WingConfig::new().unwrap_or(WingConfig {
+            ..Default::default()
+        });
+        match theme_css(v.value_of("theme").unwrap_or(&config.highlight.theme)) {
+            Ok(css) => print!("{}", css),
+            Err(e) => {
+                log(&e.to_string(), "f").unwrap();
+                std::process::exit(1);
+            }
+        };
     } else if let Some(v) = app.subcommand_matches("serve") {
-        let port: u16 = match v.value_of("port") {
-            Some(val) => val.parse().unwrap_or(8000),
-            None => 8000,
+        let port: u16 = match v.value_of("port").unwrap_or("8000").parse() {
+            Ok(port) => port,
+            Err(e) => {
+                log(&format!("Invalid port: {}", e), "f").unwrap();
+                std::process::exit(1);
+            }
         };
-        init(v.is_present("silent"), port);
+        init(ServeOptions {
+            host: String::from(v.value_of("host").unwrap_or("localhost")),
+            port,
+            open: v.is_present("open"),
+            drafts: v.is_present("drafts"),
+        });
     }
 }
@@ -0,0 +1,2 @@
+/// Minification and fingerprinting, using the configured optimisation level.
+pub mod optimise;
@@ -0,0 +1,355 @@
+// std
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+// external
+use walkdir::WalkDir;
+
+// local
+use crate::assets::assets::ignored;
+use crate::{write_if_changed, WingConfig};
+
+/// Elements whose content is copied as-is when minifying HTML
+static PRESERVED_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Attributes which can be removed without changing how a page behaves
+static UNUSED_ATTRIBUTES: [&str; 6] = [
+    " type=\"text/javascript\"",
+    " type='text/javascript'",
+    " type=\"text/css\"",
+    " type='text/css'",
+    " language=\"javascript\"",
+    " language='javascript'",
+];
+
+/// Bytes saved by optimisation
+#[derive(Debug, Default)]
+pub struct OptimisationReport {
+    /// Size of everything before optimisation
+    pub original: usize,
+    /// Size of everything after optimisation
+    pub optimised: usize,
+}
+
+impl OptimisationReport {
+    /// Number of bytes removed
+    pub fn saved(&self) -> usize {
+        self.original.saturating_sub(self.optimised)
+    }
+}
+
+/// Checks if `html` starts with the opening tag of `element`, ignoring case
+fn starts_with_tag(html: &str, element: &str) -> bool {
+    let tag_len = element.len() + 1;
+    html.len() > tag_len
+        && html.is_char_boundary(tag_len)
+        && html[1..tag_len].eq_ignore_ascii_case(element)
+        && html[tag_len..].starts_with(|c: char| c == '>' || c.is_whitespace())
+}
+
+/// Finds the first occurrence of the ASCII `needle` in `html`, ignoring case
+fn find_ignore_case(html: &str, needle: &str) -> Option<usize> {
+    html.as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Minifies HTML by removing comments and collapsing whitespace.  The content of `<pre>`, `<textarea>`, `<script>` and `<style>` elements is left untouched.
+pub fn minify_html(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") && rest.starts_with("<!--[if") == false {
+            rest = match rest.find("-->") {
+                Some(end) => &rest[end + 3..],
+                None => "",
+            };
+        } else if c == '<' {
+            match PRESERVED_ELEMENTS
+                .iter()
+                .find(|element| starts_with_tag(rest, element))
+            {
+                Some(element) => {
+                    let closing = format!("</{}", element);
+                    let end = match find_ignore_case(rest, &closing) {
+                        Some(i) => i + closing.len(),
+                        None => rest.len(),
+                    };
+                    output.push_str(&rest[..end]);
+                    rest = &rest[end..];
+                }
+                None => {
+                    output.push(c);
+                    rest = &rest[1..];
+                }
+            }
+        } else if c.is_whitespace() {
+            let end = rest
+                .find(|c: char| c.is_whitespace() == false)
+                .unwrap_or(rest.len());
+            if output.ends_with(' ') == false {
+                output.push(' ');
+            }
+            rest = &rest[end..];
+        } else {
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    output.trim().to_string()
+}
+
+/// Removes attributes that browsers ignore, like `type="text/javascript"`
+pub fn strip_unused_attributes(html: &str) -> String {
+    let mut output = String::from(html);
+    for attribute in UNUSED_ATTRIBUTES.iter() {
+        output = output.replace(attribute, "");
+    }
+    output
+}
+
+/// Copies a quoted string from the start of `rest` into `output`, returning the remaining input.  Only backtick strings can span lines.
+fn copy_string<'a>(rest: &'a str, output: &mut String) -> &'a str {
+    let quote = rest.chars().next().unwrap();
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote || (c == '\n' && quote != '`') {
+            output.push_str(&rest[..i + c.len_utf8()]);
+            return &rest[i + c.len_utf8()..];
+        }
+    }
+    output.push_str(rest);
+    ""
+}
+
+/// Minifies CSS by removing comments and unneeded whitespace
+pub fn minify_css(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("/*") {
+            rest = match rest[2..].find("*/") {
+                Some(end) => &rest[end + 4..],
+                None => "",
+            };
+        } else if c == '"' || c == '\'' {
+            rest = copy_string(rest, &mut output);
+        } else if c.is_whitespace() {
+            rest = rest.trim_start();
+            let next = rest.chars().next();
+            let previous = output.chars().last();
+            let needed = match (previous, next) {
+                (None, _) | (_, None) => false,
+                (Some(p), Some(n)) => "{};,:>".contains(p) == false && "{};,>".contains(n) == false,
+            };
+            if needed {
+                output.push(' ');
+            }
+        } else if c == '}' && output.ends_with(';') {
+            output.pop();
+            output.push(c);
+            rest = &rest[1..];
+        } else {
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    output
+}
+
+/// Minifies JavaScript by removing comments, indentation and blank lines.  Line breaks are kept, so automatic semicolon insertion still works.
+pub fn minify_js(js: &str) -> String {
+    let mut output = String::with_capacity(js.len());
+    let mut rest = js;
+
+    while let Some(c) = rest.chars().next() {
+        let previous = output.trim_end_matches(|c| c == ' ').chars().last();
+        if rest.starts_with("//") {
+            rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+        } else if rest.starts_with("/*") {
+            rest = match rest[2..].find("*/") {
+                Some(end) => &rest[end + 4..],
+                None => "",
+            };
+        } else if c == '"' || c == '\'' || c == '`' {
+            rest = copy_string(rest, &mut output);
+        } else if c == '/' && previous.map_or(true, |p| "(,=:[!&|?{};+-*%<>~^\n".contains(p)) {
+            // regular expression literal
+            let mut escaped = false;
+            let mut class = false;
+            let mut end = rest.len();
+            for (i, c) in rest.char_indices().skip(1) {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '[' {
+                    class = true;
+                } else if c == ']' {
+                    class = false;
+                } else if (c == '/' && class == false) || c == '\n' {
+                    end = i + 1;
+                    break;
+                }
+            }
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if c == '\n' || c == '\r' {
+            output.truncate(output.trim_end_matches(|c| c == ' ').len());
+            if output.len() > 0 && output.ends_with('\n') == false {
+                output.push('\n');
+            }
+            rest = &rest[1..];
+        } else if c.is_whitespace() {
+            if output.len() > 0 && output.ends_with(char::is_whitespace) == false {
+                output.push(' ');
+            }
+            rest = &rest[c.len_utf8()..];
+        } else {
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    output.trim_end().to_string()
+}
+
+/// Generates a short, stable fingerprint of some content
+pub fn fingerprint(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())[..8].to_string()
+}
+
+/// Minifies and fingerprints the CSS and JavaScript in the static directory, writing them to `static/` in the output directory.  Files matching `staticIgnore` are skipped.
+///
+/// The minified file is also written at its original name, for references that aren't rewritten, like `@import` in CSS.
+///
+/// Returns a map of the original URLs (`/static/index.css`) to the fingerprinted URLs (`/static/index.0123abcd.css`)
+pub fn optimise_static(
+    config: &WingConfig,
+    report: &mut OptimisationReport,
+) -> std::io::Result<HashMap<String, String>> {
+    let mut assets = HashMap::new();
+    let static_dir = Path::new(&config.static_dir);
+    if static_dir.is_dir() == false {
+        return Ok(assets);
+    }
+
+    let ignore = ignored(config)?;
+
+    for entry in WalkDir::new(static_dir).min_depth(1) {
+        let entry = entry?;
+        let path = entry.path();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if path.is_file() == false
+            || (extension != "css" && extension != "js")
+            || ignore.is_match(path.strip_prefix(static_dir).unwrap())
+        {
+            continue;
+        }
+
+        let original = fs::read_to_string(path)?;
+        let minified = if path
+            .to_string_lossy()
+            .ends_with(&format!(".min.{}", extension))
+        {
+            original.clone()
+        } else if extension == "css" {
+            minify_css(&original)
+        } else {
+            minify_js(&original)
+        };
+        report.original += original.len();
+        report.optimised += minified.len();
+
+        let relative = path.strip_prefix(static_dir).unwrap();
+        let fingerprinted =
+            relative.with_extension(format!("{}.{}", fingerprint(&minified), extension));
+
+        let output_dir = Path::new(&config.output_dir).join("static");
+        if let Some(parent) = output_dir.join(relative).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_if_changed(&output_dir.join(&fingerprinted), minified.as_bytes())?;
+        write_if_changed(&output_dir.join(relative), minified.as_bytes())?;
+
+        let url = |p: &Path| -> String {
+            let segments: Vec<String> = p
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            format!("/static/{}", segments.join("/"))
+        };
+        assets.insert(url(relative), url(&fingerprinted));
+    }
+
+    Ok(assets)
+}
+
+/// Replaces references to static assets with their fingerprinted versions
+pub fn fingerprint_references(html: &str, assets: &HashMap<String, String>) -> String {
+    let mut output = String::from(html);
+    for (original, fingerprinted) in assets.iter() {
+        for quote in ["\"", "'"].iter() {
+            output = output.replace(
+                &format!("{}{}{}", quote, original, quote),
+                &format!("{}{}{}", quote, fingerprinted, quote),
+            );
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minify_css, minify_html, minify_js};
+
+    #[test]
+    pub fn test_minify_html() {
+        assert_eq!(
+            minify_html(
+                "<div>\n    <!-- comment -->\n    <p>a  b</p>\n<pre>  x\n  y</pre>\n</div>"
+            ),
+            "<div> <p>a b</p> <pre>  x\n  y</pre> </div>"
+        );
+        assert_eq!(
+            minify_html("<PRE>  a </Pre>  <p> b </p>"),
+            "<PRE>  a </Pre> <p> b </p>"
+        );
+    }
+
+    #[test]
+    pub fn test_minify_css() {
+        assert_eq!(
+            minify_css("/* c */\nbody {\n    color: red;\n    content: \"a  b\";\n}\na:hover, p > b { margin: 0 auto; }"),
+            "body{color:red;content:\"a  b\"}a:hover,p>b{margin:0 auto}"
+        );
+    }
+
+    #[test]
+    pub fn test_minify_js() {
+        assert_eq!(
+            minify_js("// c\nfunction a() {\n    /* b */\n    return \"//x\".replace(/\\/\\//g, '');\n}\n\n\na();"),
+            "function a() {\nreturn \"//x\".replace(/\\/\\//g, '');\n}\na();"
+        );
+        assert_eq!(
+            minify_js("const t = `a\n  // not a comment\n  b`;\n  t;"),
+            "const t = `a\n  // not a comment\n  b`;\nt;"
+        );
+    }
+}
@@ -0,0 +1,2 @@
+/// Splitting listing pages into several pages.
+pub mod pagination;
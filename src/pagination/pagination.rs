@@ -0,0 +1,128 @@
+// external
+use serde::Serialize;
+
+// local
+use crate::links::links::page_path;
+use crate::{WingConfig, WingPageSummary};
+
+/// One page of a paginated listing
+#[derive(Serialize, Clone, Debug)]
+pub struct WingPaginator {
+    /// Pages listed on this page
+    pub pages: Vec<WingPageSummary>,
+    /// Number of this page, from `1`
+    pub current: usize,
+    /// Number of pages in the listing
+    pub total: usize,
+    /// Maximum number of pages listed on each page
+    pub paginate_by: usize,
+    /// Root-relative link to the first page
+    pub first: String,
+    /// Root-relative link to the last page
+    pub last: String,
+    /// Root-relative link to the previous page, if there is one
+    pub previous: Option<String>,
+    /// Root-relative link to the next page, if there is one
+    pub next: Option<String>,
+}
+
+/// Gets where page `number` of a listing is built, relative to the output directory.  The first page is the listing page itself, and the rest are under `page/`, e.g. `["blog", "page", "2", "index.html"]` for `["blog", "index.html"]`.
+pub fn pager_location(first: &[String], number: usize) -> Vec<String> {
+    if number <= 1 {
+        return first.to_vec();
+    }
+
+    let mut location: Vec<String> = match first.split_last() {
+        Some((_file, dirs)) => dirs.to_vec(),
+        None => vec![],
+    };
+    location.push(String::from("page"));
+    location.push(number.to_string());
+    location.push(String::from("index.html"));
+    location
+}
+
+/// Splits `pages` into pages of `paginate_by`, listed at `first` (relative to the output directory) and the locations after it.  Returns nothing if `paginate_by` is `0`.
+pub fn paginate(
+    pages: &Vec<WingPageSummary>,
+    paginate_by: usize,
+    first: &[String],
+    config: &WingConfig,
+) -> Vec<(Vec<String>, WingPaginator)> {
+    if paginate_by == 0 {
+        return vec![];
+    }
+
+    let total = std::cmp::max((pages.len() + paginate_by - 1) / paginate_by, 1);
+    let link = |number: usize| page_path(&pager_location(first, number), config);
+
+    (1..=total)
+        .map(|number| {
+            let slice = pages
+                .iter()
+                .skip((number - 1) * paginate_by)
+                .take(paginate_by)
+                .cloned()
+                .collect();
+            (
+                pager_location(first, number),
+                WingPaginator {
+                    pages: slice,
+                    current: number,
+                    total,
+                    paginate_by,
+                    first: link(1),
+                    last: link(total),
+                    previous: if number > 1 {
+                        Some(link(number - 1))
+                    } else {
+                        None
+                    },
+                    next: if number < total {
+                        Some(link(number + 1))
+                    } else {
+                        None
+                    },
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::paginate;
+    use crate::{WingConfig, WingPageSummary};
+
+    #[test]
+    pub fn test_paginate() {
+        let pages: Vec<WingPageSummary> = (0..5)
+            .map(|i| WingPageSummary::test(&format!("blog/{}", i)))
+            .collect();
+        let first = vec![String::from("blog"), String::from("index.html")];
+        let config = WingConfig {
+            url_style: String::from("directory"),
+            ..Default::default()
+        };
+
+        assert_eq!(paginate(&pages, 0, &first, &config).len(), 0);
+
+        let pagers = paginate(&pages, 2, &first, &config);
+        assert_eq!(pagers.len(), 3);
+        assert_eq!(pagers[0].0, first);
+        assert_eq!(pagers[1].0, vec!["blog", "page", "2", "index.html"]);
+
+        let (_location, second) = &pagers[1];
+        assert_eq!(second.current, 2);
+        assert_eq!(second.total, 3);
+        assert_eq!(second.pages[0].path, "blog/2");
+        assert_eq!(second.previous, Some(String::from("/blog/")));
+        assert_eq!(second.next, Some(String::from("/blog/page/3/")));
+        assert_eq!(pagers[2].1.pages.len(), 1);
+        assert_eq!(pagers[2].1.next, None);
+
+        let empty = paginate(&vec![], 10, &first, &config);
+        assert_eq!(empty.len(), 1);
+        assert_eq!(empty[0].1.last, "/blog/");
+    }
+}
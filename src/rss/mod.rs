@@ -0,0 +1,2 @@
+/// RSS feed generation.
+pub mod rss;
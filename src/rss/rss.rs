@@ -0,0 +1,157 @@
+// std
+use std::path::Path;
+
+// external
+use chrono::prelude::{DateTime, Utc};
+
+// local
+use crate::frontmatter::frontmatter::parse_date;
+use crate::links::links::{self, site_segments};
+use crate::{is_not_found, write_if_changed, WingConfig, WingTemplate};
+
+/// Escapes a string for use in XML text and attribute values
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Generates the URL of a completed page, using the configured base URL
+pub fn page_url(page: &WingTemplate, config: &WingConfig) -> String {
+    links::page_url(
+        &site_segments(Path::new(&page.completed_file), config),
+        config,
+    )
+}
+
+/// Gets the publication date of a page, preferring the `date` frontmatter over the file's creation time
+pub fn page_date(page: &WingTemplate) -> DateTime<Utc> {
+    page.frontmatter
+        .date
+        .as_deref()
+        .and_then(parse_date)
+        .unwrap_or(page.created)
+}
+
+/// Checks whether a page is in one of the configured feed sections, or a section below one.  The 404 page and section listing pages never do.
+fn in_sections(page: &WingTemplate, config: &WingConfig) -> bool {
+    if is_not_found(Path::new(&page.completed_file), config) == true || page.section.is_some() {
+        return false;
+    }
+
+    if config.feed.sections.len() == 0 {
+        return true;
+    }
+
+    in_section(&page.summary.section, &config.feed.sections)
+}
+
+/// Checks if `section`, the directory a page's content is in, is one of `sections` or below one, e.g. `blog/2020` is in `blog`
+fn in_section(section: &str, sections: &Vec<String>) -> bool {
+    sections.iter().any(|s| {
+        let s = s.trim_matches('/');
+        s.len() > 0 && (section == s || section.starts_with(&format!("{}/", s)))
+    })
+}
+
+/// Renders an RSS 2.0 channel containing the given pages, newest first
+pub fn render(
+    pages: &Vec<&WingTemplate>,
+    config: &WingConfig,
+    title: &str,
+    description: &str,
+    link: &str,
+) -> String {
+    let mut items: Vec<(&WingTemplate, DateTime<Utc>)> =
+        pages.iter().map(|p| (*p, page_date(p))).collect();
+    items.sort_by(|a, b| b.1.cmp(&a.1));
+    if config.feed.limit > 0 {
+        items.truncate(config.feed.limit);
+    }
+
+    let mut feed = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    feed.push_str("<rss version=\"2.0\">\n<channel>\n");
+    feed.push_str(&format!("<title>{}</title>\n", escape(title)));
+    feed.push_str(&format!("<link>{}</link>\n", escape(link)));
+    feed.push_str(&format!(
+        "<description>{}</description>\n",
+        escape(description)
+    ));
+    // the newest item's date, so the feed only changes when its items do
+    if let Some((_page, date)) = items.first() {
+        feed.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>\n",
+            date.to_rfc2822()
+        ));
+    }
+
+    for (page, date) in items {
+        let url = page_url(page, config);
+        feed.push_str("<item>\n");
+        feed.push_str(&format!("<title>{}</title>\n", escape(&page.summary.title)));
+        feed.push_str(&format!("<link>{}</link>\n", escape(&url)));
+        feed.push_str(&format!(
+            "<guid isPermaLink=\"true\">{}</guid>\n",
+            escape(&url)
+        ));
+        feed.push_str(&format!("<pubDate>{}</pubDate>\n", date.to_rfc2822()));
+        if let Some(description) = &page.frontmatter.description {
+            feed.push_str(&format!(
+                "<description>{}</description>\n",
+                escape(description)
+            ));
+        }
+        feed.push_str("</item>\n");
+    }
+
+    feed.push_str("</channel>\n</rss>\n");
+    feed
+}
+
+/// Writes `rss.xml` to the output directory, using the pages that were built
+pub fn generate(pages: &Vec<WingTemplate>, config: &WingConfig) -> std::io::Result<()> {
+    let included: Vec<&WingTemplate> = pages.iter().filter(|p| in_sections(p, config)).collect();
+
+    let feed = render(
+        &included,
+        config,
+        &config.feed.title,
+        &config.feed.description,
+        &config.base_url,
+    );
+
+    write_if_changed(
+        &Path::new(&config.output_dir).join("rss.xml"),
+        feed.as_bytes(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, in_section};
+    #[test]
+    pub fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+
+        let sections = vec![String::from("/blog/"), String::from("news/2020")];
+        assert_eq!(in_section("blog", &sections), true);
+        assert_eq!(in_section("blog/2020", &sections), true);
+        assert_eq!(in_section("news/2020", &sections), true);
+        assert_eq!(in_section("news", &sections), false);
+        assert_eq!(in_section("blogs", &sections), false);
+        assert_eq!(in_section("", &sections), false);
+    }
+}
@@ -0,0 +1,2 @@
+/// Sections, from the content directory's subdirectories.
+pub mod sections;
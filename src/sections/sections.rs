@@ -0,0 +1,221 @@
+// std
+use std::collections::HashMap;
+use std::path::Path;
+
+// external
+use serde::Serialize;
+
+// local
+use crate::links::links::{page_path, page_url};
+use crate::{WingConfig, WingPageSummary, WingSource, WingTemplateFrontmatter};
+
+/// File in a section's directory with the section's frontmatter and content
+pub static SECTION_INDEX: &'static str = "_index.md";
+
+/// A link to a page or section, for navigation
+#[derive(Serialize, Clone, Debug)]
+pub struct WingPageLink {
+    /// Path relative to the content directory, e.g. `blog/post`, or `blog` for a section
+    pub path: String,
+    /// `title` from the frontmatter, or the file or directory's name
+    pub title: String,
+    /// Root-relative link, which is rewritten like other links in templates
+    pub link: String,
+    /// Canonical URL
+    pub url: String,
+}
+
+/// A directory in the content directory
+#[derive(Serialize, Clone, Debug)]
+pub struct WingSection {
+    /// Directory relative to the content directory, e.g. `blog/2020` (empty for the content directory itself)
+    pub path: String,
+    /// `title` from the section's `_index.md`, or the directory's name
+    pub title: String,
+    /// Root-relative link to the section's listing page
+    pub link: String,
+    /// Canonical URL of the section's listing page
+    pub url: String,
+    /// frontmatter from the section's `_index.md`
+    pub frontmatter: WingTemplateFrontmatter,
+    /// Pages directly in the section, sorted by path
+    pub pages: Vec<WingPageSummary>,
+    /// Sections directly in the section, sorted by path
+    pub subsections: Vec<WingPageLink>,
+}
+
+impl WingSection {
+    pub fn to_link(&self) -> WingPageLink {
+        WingPageLink {
+            path: self.path.clone(),
+            title: self.title.clone(),
+            link: self.link.clone(),
+            url: self.url.clone(),
+        }
+    }
+}
+
+impl WingPageSummary {
+    pub fn to_link(&self) -> WingPageLink {
+        WingPageLink {
+            path: self.path.clone(),
+            title: self.title.clone(),
+            link: self.link.clone(),
+            url: self.url.clone(),
+        }
+    }
+}
+
+/// Title for a page or section without a `title` in its frontmatter: its file or directory name, or `Home` for the top-level section
+pub fn default_title(path: &str) -> String {
+    match path.rsplit('/').next() {
+        Some(n) if n.len() > 0 => String::from(n),
+        _ => String::from("Home"),
+    }
+}
+
+/// Gets the section containing `path`, e.g. `blog` for `blog/post`, or `None` for the top-level section
+pub fn parent_path(path: &str) -> Option<String> {
+    if path.len() == 0 {
+        return None;
+    }
+
+    match path.rfind('/') {
+        Some(i) => Some(String::from(&path[..i])),
+        None => Some(String::new()),
+    }
+}
+
+/// Builds the section tree from the content directory's subdirectories (as paths relative to it), the pages, and the sections' `_index.md` files
+pub fn collect(
+    dirs: &Vec<String>,
+    pages: &Vec<WingPageSummary>,
+    indexes: &Vec<&WingSource>,
+    config: &WingConfig,
+) -> HashMap<String, WingSection> {
+    let mut sections: HashMap<String, WingSection> = HashMap::new();
+    for dir in dirs.iter() {
+        let index = indexes
+            .iter()
+            .find(|s| s.section.as_ref() == Some(dir))
+            .map(|s| s.frontmatter.clone());
+        let frontmatter = index.unwrap_or(WingTemplateFrontmatter {
+            ..Default::default()
+        });
+
+        let mut location: Vec<String> = dir
+            .split('/')
+            .filter(|s| s.len() > 0)
+            .map(String::from)
+            .collect();
+        location.push(String::from("index.html"));
+
+        sections.insert(
+            dir.clone(),
+            WingSection {
+                path: dir.clone(),
+                title: frontmatter.title.clone().unwrap_or(default_title(dir)),
+                link: page_path(&location, config),
+                url: page_url(&location, config),
+                frontmatter,
+                pages: vec![],
+                subsections: vec![],
+            },
+        );
+    }
+
+    for page in pages.iter() {
+        if let Some(section) = sections.get_mut(&page.section) {
+            section.pages.push(page.clone());
+        }
+    }
+
+    let mut children: Vec<(String, WingPageLink)> = sections
+        .values()
+        .filter_map(|s| parent_path(&s.path).map(|parent| (parent, s.to_link())))
+        .collect();
+    children.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+    for (parent, child) in children.into_iter() {
+        if let Some(section) = sections.get_mut(&parent) {
+            section.subsections.push(child);
+        }
+    }
+
+    for section in sections.values_mut() {
+        section.pages.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    sections
+}
+
+/// Gets the sections above `path`, from the top-level section down to its parent
+pub fn ancestors(path: &str, sections: &HashMap<String, WingSection>) -> Vec<WingPageLink> {
+    let mut ancestors = Vec::new();
+    let mut current = parent_path(path);
+    while let Some(p) = current {
+        if let Some(section) = sections.get(&p) {
+            ancestors.push(section.to_link());
+        }
+        current = parent_path(&p);
+    }
+
+    ancestors.reverse();
+    ancestors
+}
+
+/// Gets a section's directory under the content directory
+pub fn section_dir(path: &str, config: &WingConfig) -> std::path::PathBuf {
+    path.split('/')
+        .filter(|s| s.len() > 0)
+        .fold(Path::new(&config.content_dir).to_path_buf(), |dir, s| {
+            dir.join(s)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ancestors, collect, parent_path};
+    use crate::{WingConfig, WingPageSummary};
+
+    #[test]
+    pub fn test_sections() {
+        assert_eq!(parent_path(""), None);
+        assert_eq!(parent_path("blog"), Some(String::new()));
+        assert_eq!(parent_path("blog/2020"), Some(String::from("blog")));
+
+        let config = WingConfig {
+            ..Default::default()
+        };
+        let dirs = vec![
+            String::new(),
+            String::from("blog"),
+            String::from("blog/2020"),
+        ];
+        let pages = vec![
+            WingPageSummary::test("index"),
+            WingPageSummary::test("blog/b"),
+            WingPageSummary::test("blog/a"),
+            WingPageSummary::test("blog/2020/c"),
+        ];
+        let sections = collect(&dirs, &pages, &vec![], &config);
+
+        let blog = &sections["blog"];
+        assert_eq!(blog.title, "blog");
+        assert_eq!(blog.link, "/blog/index.html");
+        assert_eq!(
+            blog.pages
+                .iter()
+                .map(|p| p.path.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["blog/a", "blog/b"]
+        );
+        assert_eq!(blog.subsections[0].path, "blog/2020");
+        assert_eq!(sections[""].subsections[0].path, "blog");
+
+        let trail: Vec<String> = ancestors("blog/2020/c", &sections)
+            .into_iter()
+            .map(|l| l.title)
+            .collect();
+        assert_eq!(trail, vec!["Home", "blog", "2020"]);
+    }
+}
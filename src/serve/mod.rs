@@ -1,2 +1,4 @@
 /// Watch and live-reload a site.
+pub mod reload;
 pub mod serve;
+pub mod watch;
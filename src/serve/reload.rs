@@ -0,0 +1,154 @@
+// std
+use std::cmp::min;
+use std::io::{Error, ErrorKind, Read};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// crates
+use rocket::{
+    http::ContentType,
+    response::{content::Content, Stream},
+    *,
+};
+
+// local
+use wsg::{rss::rss::escape, WingError};
+
+/// Script injected into served pages, which listens for rebuilds
+pub static RELOAD_SCRIPT: &'static str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/static/reload.js"));
+
+/// Shown over served pages while the latest rebuild has failed
+static ERROR_OVERLAY: &'static str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/static/error_overlay.html"
+));
+
+/// Time between keep-alive messages, so closed connections are noticed
+static KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Tells open pages when the site has been rebuilt
+pub struct LiveReload {
+    /// Number of rebuilds, and the message sent after the latest one
+    state: Mutex<(u64, String)>,
+    changed: Condvar,
+    /// Why the latest rebuild failed, if it did
+    error: Mutex<Option<String>>,
+}
+
+impl LiveReload {
+    pub fn new() -> LiveReload {
+        LiveReload {
+            state: Mutex::new((0, String::new())),
+            changed: Condvar::new(),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Clears any error overlay and tells open pages what changed after a successful rebuild
+    pub fn succeed(&self, message: &str) {
+        // pages showing the overlay need a full reload to clear it
+        match self.error.lock().unwrap().take() {
+            Some(_) => self.notify("reload"),
+            None => self.notify(message),
+        }
+    }
+
+    /// Shows `error` over every served page until the next successful rebuild
+    pub fn fail(&self, error: &WingError) {
+        *self.error.lock().unwrap() = Some(error.to_string());
+        self.notify("reload");
+    }
+
+    /// Adds the live reload script, and the error overlay if the latest rebuild failed, to an HTML page
+    pub fn inject(&self, html: &str) -> String {
+        let mut injected = String::new();
+        if let Some(error) = self.error.lock().unwrap().as_ref() {
+            injected.push_str(&ERROR_OVERLAY.replace("WING_ERROR", &escape(error)));
+        }
+        injected.push_str(RELOAD_SCRIPT);
+
+        match html.rfind("</body>") {
+            Some(i) => format!("{}{}{}", &html[..i], injected, &html[i..]),
+            None => format!("{}{}", html, injected),
+        }
+    }
+
+    /// Sends a message to all open pages: `reload`, or `css:<path>` to only reload a stylesheet
+    pub fn notify(&self, message: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.0 += 1;
+        state.1 = String::from(message);
+        self.changed.notify_all();
+    }
+
+    /// Waits for a message newer than `seen`, returning `None` if there wasn't one before `timeout`
+    fn wait(&self, seen: u64, timeout: Duration) -> (u64, Option<String>) {
+        let state = self.state.lock().unwrap();
+        let (state, _timeout) = self
+            .changed
+            .wait_timeout_while(state, timeout, |s| s.0 == seen)
+            .unwrap();
+
+        if state.0 == seen {
+            (seen, None)
+        } else {
+            (state.0, Some(state.1.clone()))
+        }
+    }
+
+    fn latest(&self) -> u64 {
+        self.state.lock().unwrap().0
+    }
+}
+
+/// Server-sent event stream of a [`LiveReload`]'s messages
+pub struct EventStream {
+    reload: Arc<LiveReload>,
+    seen: u64,
+    pending: Vec<u8>,
+    flush: bool,
+}
+
+impl Read for EventStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.len() == 0 {
+            // with Rocket's `sse` feature, `WouldBlock` flushes what has been written so far
+            if self.flush {
+                self.flush = false;
+                return Err(Error::new(ErrorKind::WouldBlock, "flush"));
+            }
+
+            let (seen, message) = self.reload.wait(self.seen, KEEP_ALIVE);
+            self.seen = seen;
+            self.pending = match message {
+                Some(m) => format!("data: {}\n\n", m),
+                None => String::from(": keep-alive\n\n"),
+            }
+            .into_bytes();
+        }
+
+        let len = min(buf.len(), self.pending.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        if self.pending.len() == 0 {
+            self.flush = true;
+        }
+
+        Ok(len)
+    }
+}
+
+#[get("/__wing/events")]
+pub fn events(reload: State<Arc<LiveReload>>) -> Content<Stream<EventStream>> {
+    let reload = reload.inner().clone();
+    Content(
+        ContentType::new("text", "event-stream"),
+        Stream::from(EventStream {
+            seen: reload.latest(),
+            reload,
+            pending: String::from("retry: 1000\n\n").into_bytes(),
+            flush: false,
+        }),
+    )
+}
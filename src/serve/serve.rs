@@ -1,34 +1,90 @@
 // std
-use std::path::Path;
+use std::fs;
+use std::io::Cursor;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::Arc;
 
 // crates
-use hotwatch::{Event, Hotwatch};
 use open::that;
 use rocket::{
     config::{Config, Environment},
+    fairing::AdHoc,
     http::ContentType,
-    response::content::Content,
+    response::{content::Content, NamedFile},
     Catcher, Request, Rocket, Route, *,
 }; // todo: figure out where catch macros are and import them instead of using glob
-use rocket_contrib::serve::StaticFiles;
+use rocket_contrib::serve::{Options, StaticFiles};
 
 // local
-use wsg::{build, log};
+use super::reload::{events, LiveReload};
+use super::watch::watch;
+use wsg::{build, log, WingConfig, NOT_FOUND_PAGE};
 
 static NOT_FOUND: &'static str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/src/static/not_found.html"
 ));
 
+/// Serves the site's `404.html`, or the built-in page if the site doesn't have one
 #[catch(404)]
-fn not_found() -> Content<&'static str> {
-    Content(ContentType::HTML, NOT_FOUND)
+fn not_found(req: &Request) -> Content<String> {
+    let page = req
+        .guard::<State<WingConfig>>()
+        .succeeded()
+        .and_then(|config| {
+            fs::read_to_string(Path::new(&config.output_dir).join(NOT_FOUND_PAGE)).ok()
+        })
+        .unwrap_or(String::from(NOT_FOUND));
+    Content(ContentType::HTML, page)
+}
+
+/// Serves `/a/b` from `a/b.html` if there's no `a/b` file or directory, like most static hosts
+#[get("/<path..>", rank = 20)]
+fn extensionless(path: PathBuf, config: State<WingConfig>) -> Option<NamedFile> {
+    NamedFile::open(
+        Path::new(&config.output_dir)
+            .join(path)
+            .with_extension("html"),
+    )
+    .ok()
+}
+
+/// How and where to serve the site
+pub struct ServeOptions {
+    /// Address to bind to
+    pub host: String,
+    /// Preferred port; the next free one is used if it's taken
+    pub port: u16,
+    /// If `true`, the site is opened in the browser
+    pub open: bool,
+    /// If `true`, drafts and scheduled pages are built
+    pub drafts: bool,
+}
+
+/// Finds the first port from `port` that can be bound on `host`
+fn free_port(host: &str, port: u16) -> Option<u16> {
+    (port..=u16::MAX)
+        .take(100)
+        .find(|p| TcpListener::bind((host, *p)).is_ok())
+}
+
+/// URL the site can be opened at; wildcard addresses can't be browsed to, so they use `localhost`
+fn site_url(host: &str, port: u16) -> String {
+    match host {
+        "0.0.0.0" | "::" | "[::]" => format!("http://localhost:{}", port),
+        h if h.contains(':') => format!("http://[{}]:{}", h, port),
+        h => format!("http://{}:{}", h, port),
+    }
 }
 
-/// If `open` is set to true, the site will **not** be opened automatically.
-pub fn init(open: bool, port: u16) {
-    if Path::new("site/").is_dir() == false {
+pub fn init(options: ServeOptions) {
+    let config = WingConfig::new().unwrap_or(WingConfig {
+        ..Default::default()
+    });
+
+    if Path::new(&config.output_dir).is_dir() == false {
         log(
             &String::from("Failed to start watching as site directory doesn't exist."),
             "f",
@@ -36,31 +92,78 @@ pub fn init(open: bool, port: u16) {
         .unwrap();
         exit(1);
     } else {
-        build(None, None);
-
-        let mut hw = Hotwatch::new().expect("Failed to initialise file watcher");
-        hw.watch("./", |e: Event| {
-            if let Event::Write(_path) = e {
-                log(&String::from("to build site"), "starting").unwrap();
-                build(None, None);
-                log(&String::from("Rebuilt site!"), "s").unwrap();
+        let reload = Arc::new(LiveReload::new());
+
+        // keep serving the previous build, with the error shown over it
+        if let Err(e) = build(None, None, options.drafts) {
+            log(&e.to_string(), "f").unwrap();
+            reload.fail(&e);
+        }
+
+        let _watcher = watch(config.clone(), reload.clone(), options.drafts);
+
+        let port = match free_port(&options.host, options.port) {
+            Some(p) => p,
+            None => {
+                log(
+                    &format!(
+                        "Failed to find a free port on {} from {}.",
+                        options.host, options.port
+                    ),
+                    "f",
+                )
+                .unwrap();
+                exit(1);
             }
-        })
-        .expect("Failed to watch directory.");
+        };
+        let url = site_url(&options.host, port);
+        log(&format!("Serving site at {}", url), "s").unwrap();
 
-        if open == false {
-            that("http://localhost:8000").expect("Failed to open in browser.");
+        if options.open == true {
+            that(&url).expect("Failed to open in browser.");
         }
 
+        // each open page keeps a worker busy, listening for reloads
         let rocket_config = Config::build(Environment::Production)
+            .address(options.host.as_str())
             .port(port)
+            .workers(64)
             .finalize()
             .unwrap();
 
+        let page_reload = reload.clone();
         rocket::custom(rocket_config)
-            .mount("/static/", StaticFiles::from("static/").rank(-1))
-            .mount("/", StaticFiles::from("site/"))
+            .manage(reload)
+            .manage(config.clone())
+            .mount("/", routes![events, extensionless])
+            // `/a/b` redirects to `/a/b/` if it's a directory, so relative links resolve from inside it
+            .mount(
+                "/",
+                StaticFiles::new(&config.output_dir, Options::Index | Options::NormalizeDirs),
+            )
             .register(catchers![not_found])
+            .attach(AdHoc::on_response("Live reload", move |_req, res| {
+                if res.content_type() == Some(ContentType::HTML) {
+                    if let Some(body) = res.body_string() {
+                        res.set_sized_body(Cursor::new(page_reload.inject(&body)));
+                    }
+                }
+            }))
             .launch();
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::site_url;
+
+    #[test]
+    pub fn test_site_url() {
+        assert_eq!(site_url("127.0.0.1", 80), "http://127.0.0.1:80");
+        assert_eq!(site_url("example.test", 8000), "http://example.test:8000");
+        assert_eq!(site_url("0.0.0.0", 80), "http://localhost:80");
+        assert_eq!(site_url("::", 80), "http://localhost:80");
+        assert_eq!(site_url("[::]", 80), "http://localhost:80");
+        assert_eq!(site_url("::1", 80), "http://[::1]:80");
+    }
+}
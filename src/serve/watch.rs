@@ -0,0 +1,239 @@
+// std
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc::channel, Arc};
+use std::thread;
+use std::time::Duration;
+
+// crates
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use hotwatch::{Event, Hotwatch};
+
+// local
+use super::reload::LiveReload;
+use wsg::{build, cache::cache::CACHE_FILE, log, WingConfig};
+
+/// How long the project has to be quiet for before rebuilding, so bursts of changes only cause one build
+static DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Swap, backup and lock files written by editors, which are never part of the site
+static EDITOR_FILES: [&'static str; 7] = [
+    "**/*.swp",
+    "**/*.swx",
+    "**/*~",
+    "**/.#*",
+    "**/#*#",
+    "**/4913",
+    "**/.git/**",
+];
+
+/// Decides which changed paths should trigger a rebuild
+struct Filter {
+    root: PathBuf,
+    output_dir: PathBuf,
+    cache_file: PathBuf,
+    ignored: GlobSet,
+}
+
+/// Canonical form of `path`, so it can be compared with the absolute paths in events.  Paths that don't exist yet are joined onto `root`.
+fn absolute(root: &Path, path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| root.join(path))
+}
+
+impl Filter {
+    fn new(config: &WingConfig) -> Result<Filter, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in EDITOR_FILES
+            .iter()
+            .map(|p| *p)
+            .chain(config.watch_ignore.iter().map(|p| p.as_str()))
+        {
+            builder.add(Glob::new(pattern)?);
+        }
+
+        let root = fs::canonicalize(".").unwrap_or(PathBuf::from("."));
+        Ok(Filter {
+            output_dir: absolute(&root, Path::new(&config.output_dir)),
+            cache_file: absolute(&root, Path::new(CACHE_FILE)),
+            root,
+            ignored: builder.build()?,
+        })
+    }
+
+    /// Path relative to the project root, as events report absolute paths
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    fn is_relevant(&self, path: &Path) -> bool {
+        path.starts_with(&self.output_dir) == false
+            && path != self.cache_file
+            && self.ignored.is_match(self.relative(path)) == false
+    }
+}
+
+/// Paths touched by a watcher event
+fn changed_paths(event: Event) -> Vec<PathBuf> {
+    match event {
+        Event::Create(path) | Event::Write(path) | Event::Remove(path) => vec![path],
+        Event::Rename(from, to) => vec![from, to],
+        // events were missed, so anything could have changed
+        Event::Rescan => vec![PathBuf::from(".")],
+        _ => vec![],
+    }
+}
+
+/// Works out what open pages need to reload after `changed` paths changed: `css:<url>` if only a stylesheet in the static directory changed, or `reload` otherwise
+fn reload_message(changed: &Vec<PathBuf>, config: &WingConfig) -> String {
+    let stylesheet = |path: &PathBuf| -> Option<String> {
+        if path.extension()? != "css" || config.optimisation_level == "high" {
+            return None;
+        }
+        let static_dir = fs::canonicalize(&config.static_dir).ok()?;
+        let path = fs::canonicalize(path).ok()?;
+        let segments: Vec<String> = path
+            .strip_prefix(static_dir)
+            .ok()?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        Some(format!("css:/static/{}", segments.join("/")))
+    };
+
+    let stylesheets: Vec<Option<String>> = changed.iter().map(stylesheet).collect();
+    match stylesheets.as_slice() {
+        [Some(message)] => message.clone(),
+        _ => String::from("reload"),
+    }
+}
+
+/// Rebuilds the site after changes, telling open pages once it's done
+fn rebuild(changed: &Vec<PathBuf>, config: &WingConfig, reload: &LiveReload, drafts: bool) {
+    log(
+        &format!("to build site ({} file(s) changed)", changed.len()),
+        "starting",
+    )
+    .unwrap();
+    match build(None, None, drafts) {
+        Ok(report) => {
+            log(
+                &format!(
+                    "Rebuilt site! ({} page(s) rendered, {} unchanged)",
+                    report.pages, report.unchanged
+                ),
+                "s",
+            )
+            .unwrap();
+            reload.succeed(&reload_message(changed, config));
+        }
+        Err(e) => {
+            log(&e.to_string(), "f").unwrap();
+            reload.fail(&e);
+        }
+    };
+}
+
+/// Watches the project for changes, rebuilding once they settle.  Changes to the output directory, the build cache, editor swap files and anything matching `watchIgnore` are ignored.
+///
+/// The returned watcher stops watching when dropped.
+pub fn watch(config: WingConfig, reload: Arc<LiveReload>, drafts: bool) -> Hotwatch {
+    let filter = Filter::new(&config).unwrap_or_else(|e| {
+        log(&format!("Invalid watchIgnore glob: {}", e), "f").unwrap();
+        Filter::new(&WingConfig {
+            output_dir: config.output_dir.clone(),
+            ..Default::default()
+        })
+        .unwrap()
+    });
+
+    let (sender, receiver) = channel::<PathBuf>();
+    let mut hw =
+        Hotwatch::new_with_custom_delay(DEBOUNCE).expect("Failed to initialise file watcher");
+    hw.watch("./", move |e: Event| {
+        for path in changed_paths(e) {
+            if filter.is_relevant(&path) {
+                // the receiver only goes away with the server
+                sender.send(path).ok();
+            }
+        }
+    })
+    .expect("Failed to watch directory.");
+
+    thread::spawn(move || {
+        while let Ok(first) = receiver.recv() {
+            let mut changed = vec![first];
+            while let Ok(path) = receiver.recv_timeout(DEBOUNCE) {
+                if changed.contains(&path) == false {
+                    changed.push(path);
+                }
+            }
+
+            rebuild(&changed, &config, &reload, drafts);
+        }
+    });
+
+    hw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reload_message, Filter};
+    use std::fs;
+    use std::path::PathBuf;
+    use wsg::{cache::cache::CACHE_FILE, WingConfig};
+
+    #[test]
+    pub fn test_filter() {
+        let filter = Filter::new(&WingConfig {
+            watch_ignore: vec![String::from("notes/**")],
+            ..Default::default()
+        })
+        .unwrap();
+        let root = fs::canonicalize(".").unwrap();
+
+        assert_eq!(filter.is_relevant(&root.join("content/index.md")), true);
+        assert_eq!(filter.is_relevant(&root.join("static/index.css")), true);
+        assert_eq!(filter.is_relevant(&root.join("site/index.html")), false);
+        assert_eq!(filter.is_relevant(&root.join(CACHE_FILE)), false);
+        assert_eq!(
+            filter.is_relevant(&root.join("content/.index.md.swp")),
+            false
+        );
+        assert_eq!(filter.is_relevant(&root.join("notes/todo.md")), false);
+    }
+
+    #[test]
+    pub fn test_reload_message() {
+        let static_dir = std::env::temp_dir().join("wsg-reload-message");
+        fs::create_dir_all(static_dir.join("css")).unwrap();
+        let stylesheet = static_dir.join("css").join("index.css");
+        let script = static_dir.join("index.js");
+        fs::write(&stylesheet, "body{}").unwrap();
+        fs::write(&script, "").unwrap();
+
+        let config = WingConfig {
+            static_dir: static_dir.display().to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            reload_message(&vec![stylesheet.clone()], &config),
+            "css:/static/css/index.css"
+        );
+        assert_eq!(reload_message(&vec![script.clone()], &config), "reload");
+        assert_eq!(
+            reload_message(&vec![stylesheet.clone(), script], &config),
+            "reload"
+        );
+        assert_eq!(
+            reload_message(&vec![PathBuf::from("content/index.css")], &config),
+            "reload"
+        );
+
+        // fingerprinted stylesheets can't be swapped in place
+        let optimised = WingConfig {
+            optimisation_level: String::from("high"),
+            ..config
+        };
+        assert_eq!(reload_message(&vec![stylesheet], &optimised), "reload");
+    }
+}
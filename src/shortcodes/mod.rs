@@ -0,0 +1,2 @@
+/// Shortcodes, i.e. templates called from MarkDown.
+pub mod shortcodes;
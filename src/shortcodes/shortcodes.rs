@@ -0,0 +1,317 @@
+// std
+use std::ops::Range;
+
+// external
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use serde_json::{Map, Value};
+use tera::{Context, Tera};
+
+// local
+use crate::highlight::highlight::code_blocks;
+use crate::links::links::rewrite_html;
+use crate::rss::rss::escape;
+use crate::WingConfig;
+
+/// A shortcode's opening or closing tag, e.g. `{{< note type="warn" >}}` or `{{< /note >}}`
+#[derive(Debug, PartialEq)]
+struct ShortcodeTag {
+    name: String,
+    args: Map<String, Value>,
+    closing: bool,
+    /// Length of the tag in the MarkDown
+    len: usize,
+}
+
+/// A rendered shortcode, replaced by a placeholder until the MarkDown has been rendered
+#[derive(Debug)]
+pub struct Shortcode {
+    /// The shortcode as written in the MarkDown, including its body
+    pub source: String,
+    /// HTML rendered from the shortcode's template
+    pub rendered: String,
+}
+
+fn is_name(name: &str) -> bool {
+    name.len() > 0
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() == true || c == '_' || c == '-')
+}
+
+/// Parses arguments written as `key="value"`, `key=3` or `key=true`
+fn parse_args(raw: &str) -> Option<Map<String, Value>> {
+    let mut args = Map::new();
+    let mut rest = raw.trim_start();
+    while rest.len() > 0 {
+        let equals = rest.find('=')?;
+        let key = rest[..equals].trim();
+        if is_name(key) == false {
+            return None;
+        }
+
+        rest = rest[equals + 1..].trim_start();
+        let (value, remaining) = if rest.starts_with('"') {
+            // strings end at the next unescaped quote
+            let mut value = String::new();
+            let mut escaped = false;
+            let mut end = None;
+            for (i, c) in rest.char_indices().skip(1) {
+                if escaped == true {
+                    value.push(c);
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    end = Some(i + 1);
+                    break;
+                } else {
+                    value.push(c);
+                }
+            }
+            (Value::String(value), &rest[end?..])
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let raw = &rest[..end];
+            let value = serde_json::from_str::<Value>(raw)
+                .ok()
+                .filter(|v| v.is_number() || v.is_boolean())
+                .unwrap_or(Value::String(String::from(raw)));
+            (value, &rest[end..])
+        };
+
+        args.insert(String::from(key), value);
+        rest = remaining.trim_start();
+    }
+
+    Some(args)
+}
+
+/// Parses the shortcode tag at the start of `source`, if there is one
+fn parse_tag(source: &str) -> Option<ShortcodeTag> {
+    if source.starts_with("{{<") == false {
+        return None;
+    }
+
+    let end = source.find(">}}")?;
+    let inner = source[3..end].trim();
+    let closing = inner.starts_with('/');
+    let inner = inner.trim_start_matches('/').trim_start();
+    let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+    let name = &inner[..name_end];
+    if is_name(name) == false {
+        return None;
+    }
+
+    Some(ShortcodeTag {
+        name: String::from(name),
+        args: if closing == true {
+            Map::new()
+        } else {
+            parse_args(&inner[name_end..])?
+        },
+        closing,
+        len: end + 3,
+    })
+}
+
+/// Finds the code blocks and code spans in `markdown`, where shortcodes are left as they are
+fn code_ranges(markdown: &str) -> Vec<Range<usize>> {
+    let mut options = Options::empty();
+    options.insert(Options::all());
+    Parser::new_ext(markdown, options)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the shortcode tags in `markdown`, with where they start
+fn tags(markdown: &str) -> Vec<(usize, ShortcodeTag)> {
+    let code = code_ranges(markdown);
+    markdown
+        .match_indices("{{<")
+        .filter(|(i, _m)| code.iter().any(|r| r.contains(i)) == false)
+        .filter_map(|(i, _m)| parse_tag(&markdown[i..]).map(|tag| (i, tag)))
+        .collect()
+}
+
+/// Gets the names of the shortcodes used in `markdown`
+pub fn names(markdown: &str) -> Vec<String> {
+    let mut names: Vec<String> = tags(markdown)
+        .into_iter()
+        .filter(|(_i, tag)| tag.closing == false)
+        .map(|(_i, tag)| tag.name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Renders MarkDown in a shortcode's body, including any shortcodes in it, with links rewritten like the page's
+fn render_body(
+    body: &str,
+    tera: &Tera,
+    page: &[String],
+    source: &[String],
+    config: &WingConfig,
+) -> tera::Result<String> {
+    let (markdown, shortcodes) = expand(body, tera, page, source, config)?;
+    let mut options = Options::empty();
+    options.insert(Options::all());
+    let parser = Parser::new_ext(&markdown, options);
+
+    let mut output = String::new();
+    if config.highlight.enabled == true {
+        html::push_html(
+            &mut output,
+            code_blocks(parser, &config.highlight).into_iter(),
+        );
+    } else {
+        html::push_html(&mut output, parser);
+    }
+    Ok(substitute(
+        &rewrite_html(&output, page, source, config),
+        &shortcodes,
+    ))
+}
+
+fn placeholder(n: usize) -> String {
+    format!("<!--wing-shortcode-{}-->", n)
+}
+
+/// Renders the shortcodes in `markdown` with `templates/shortcodes/<name>.html`, replacing them with placeholders for `substitute` to fill in once the MarkDown has been rendered.  Shortcodes in code blocks and code spans are left alone.
+///
+/// Templates get the shortcode's arguments, and for shortcodes with a closing tag, the MarkDown between the tags as `body` and rendered to HTML as `content`.  Links in `content` are rewritten for the page being rendered to `page`, from the MarkDown at `source`.
+pub fn expand(
+    markdown: &str,
+    tera: &Tera,
+    page: &[String],
+    source: &[String],
+    config: &WingConfig,
+) -> tera::Result<(String, Vec<Shortcode>)> {
+    let tags = tags(markdown);
+    let mut output = String::with_capacity(markdown.len());
+    let mut shortcodes = Vec::new();
+    let mut position = 0;
+    let mut next = 0;
+    while next < tags.len() {
+        let (start, tag) = &tags[next];
+        next += 1;
+        if tag.closing == true {
+            // unmatched, so left as it is
+            continue;
+        }
+
+        // the matching closing tag, skipping over nested shortcodes with the same name
+        let mut depth = 0;
+        let mut close = None;
+        for (i, (_start, other)) in tags.iter().enumerate().skip(next) {
+            if other.name == tag.name && other.closing == false {
+                depth += 1;
+            } else if other.name == tag.name && other.closing == true {
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+
+        let mut context = Context::from_value(Value::Object(tag.args.clone()))?;
+        let body_start = start + tag.len;
+        let end = match close {
+            Some(i) => {
+                let (close_start, close_tag) = &tags[i];
+                let body = &markdown[body_start..*close_start];
+                context.insert("body", body);
+                context.insert("content", &render_body(body, tera, page, source, config)?);
+                next = i + 1;
+                close_start + close_tag.len
+            }
+            None => body_start,
+        };
+
+        let rendered = tera.render(&format!("shortcodes/{}.html", tag.name), &context)?;
+        output.push_str(&markdown[position..*start]);
+        output.push_str(&placeholder(shortcodes.len()));
+        shortcodes.push(Shortcode {
+            source: String::from(&markdown[*start..end]),
+            rendered,
+        });
+        position = end;
+    }
+
+    output.push_str(&markdown[position..]);
+    Ok((output, shortcodes))
+}
+
+/// Replaces the placeholders left by `expand` with the rendered shortcodes.  Placeholders that ended up as text (e.g. in a code span) are shown as the shortcode was written.
+pub fn substitute(html: &str, shortcodes: &Vec<Shortcode>) -> String {
+    let mut output = String::from(html);
+    for (n, shortcode) in shortcodes.iter().enumerate() {
+        let placeholder = placeholder(n);
+        output = output
+            .replace(&placeholder, &shortcode.rendered)
+            .replace(&escape(&placeholder), &escape(&shortcode.source));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, names, parse_tag, substitute};
+    use crate::WingConfig;
+    use pulldown_cmark::{html, Parser};
+    use tera::Tera;
+
+    #[test]
+    pub fn test_shortcodes() {
+        let tag =
+            parse_tag("{{< note type=\"warn \\\"x\\\"\" level=2 open=true id=a >}} rest").unwrap();
+        assert_eq!(tag.name, "note");
+        assert_eq!(tag.args["type"], "warn \"x\"");
+        assert_eq!(tag.args["level"], 2);
+        assert_eq!(tag.args["open"], true);
+        assert_eq!(tag.args["id"], "a");
+        assert_eq!(tag.len, 53);
+        assert_eq!(parse_tag("{{< /note >}}").unwrap().closing, true);
+        assert_eq!(parse_tag("{{< not a shortcode >}}"), None);
+        assert_eq!(parse_tag("{{< note"), None);
+
+        let mut tera = Tera::default();
+        tera.autoescape_on(vec![]);
+        tera.add_raw_templates(vec![
+            (
+                "shortcodes/note.html",
+                "<div class=\"{{ type }}\">{{ content }}</div>",
+            ),
+            (
+                "shortcodes/video.html",
+                "<iframe src=\"{{ id }}\"></iframe>",
+            ),
+        ])
+        .unwrap();
+        let config = WingConfig {
+            ..Default::default()
+        };
+
+        let markdown = "{{< note type=\"warn\" >}}\n**Careful** {{< video id=\"x\" >}}\n\n{{< note type=\"inner\" >}}hi{{< /note >}}\n{{< /note >}}\n\nUse `{{< video id=\"y\" >}}`\n\n```\n{{< video >}}\n```\n";
+        assert_eq!(names(markdown), vec!["note", "video"]);
+
+        let page = vec![String::from("a"), String::from("b.html")];
+        let (expanded, shortcodes) = expand(markdown, &tera, &page, &page, &config).unwrap();
+        assert_eq!(shortcodes.len(), 1);
+        let mut output = String::new();
+        html::push_html(&mut output, Parser::new(&expanded));
+        let output = substitute(&output, &shortcodes);
+        assert!(output.starts_with(
+            "<div class=\"warn\"><p><strong>Careful</strong> <iframe src=\"x\"></iframe></p>\n<div class=\"inner\"><p>hi</p>\n</div>\n</div>"
+        ));
+        assert!(output.contains("<code>{{&lt; video id=&quot;y&quot; &gt;}}</code>"));
+        assert!(output.contains("<pre><code>{{&lt; video &gt;}}\n</code></pre>"));
+
+        assert!(expand("{{< missing >}}", &tera, &page, &page, &config).is_err());
+    }
+}
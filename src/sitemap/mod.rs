@@ -0,0 +1,2 @@
+/// XML sitemap generation.
+pub mod sitemap;
@@ -0,0 +1,80 @@
+// std
+use std::path::{Path, PathBuf};
+
+// external
+use chrono::SecondsFormat;
+
+// local
+use crate::links::links::{self, site_segments};
+use crate::rss::rss::{escape, page_url};
+use crate::{is_not_found, write_if_changed, WingConfig, WingTemplate};
+
+/// Renders a sitemap following the sitemaps.org schema.  Pages with `sitemap: false` in their frontmatter, and the 404 page, are excluded.  `listings` are generated pages that aren't built from content, like taxonomy pages, so have no last modified time.
+pub fn render(pages: &Vec<WingTemplate>, listings: &Vec<PathBuf>, config: &WingConfig) -> String {
+    let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    sitemap.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+
+    for page in pages.iter().filter(|p| {
+        p.frontmatter.sitemap == true && is_not_found(Path::new(&p.completed_file), config) == false
+    }) {
+        // a paginated section's later pages are listed along with its listing page
+        let urls = std::iter::once(page_url(page, config)).chain(
+            page.pagers
+                .iter()
+                .map(|p| links::page_url(&site_segments(Path::new(p), config), config)),
+        );
+        for url in urls {
+            sitemap.push_str("<url>\n");
+            sitemap.push_str(&format!("<loc>{}</loc>\n", escape(&url)));
+            sitemap.push_str(&format!(
+                "<lastmod>{}</lastmod>\n",
+                page.modified.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+            if let Some(changefreq) = &page.frontmatter.changefreq {
+                sitemap.push_str(&format!(
+                    "<changefreq>{}</changefreq>\n",
+                    escape(changefreq)
+                ));
+            }
+            if let Some(priority) = page.frontmatter.priority {
+                sitemap.push_str(&format!(
+                    "<priority>{:.1}</priority>\n",
+                    priority.max(0.0).min(1.0)
+                ));
+            }
+            sitemap.push_str("</url>\n");
+        }
+    }
+
+    for listing in listings.iter() {
+        sitemap.push_str(&format!(
+            "<url>\n<loc>{}</loc>\n</url>\n",
+            escape(&links::page_url(&site_segments(listing, config), config))
+        ));
+    }
+
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+/// Writes `sitemap.xml` to the output directory, and a `robots.txt` pointing to it
+pub fn generate(
+    pages: &Vec<WingTemplate>,
+    listings: &Vec<PathBuf>,
+    config: &WingConfig,
+) -> std::io::Result<()> {
+    write_if_changed(
+        &Path::new(&config.output_dir).join("sitemap.xml"),
+        render(pages, listings, config).as_bytes(),
+    )?;
+
+    write_if_changed(
+        &Path::new(&config.output_dir).join("robots.txt"),
+        format!(
+            "User-agent: *\nAllow: /\n\nSitemap: {}/sitemap.xml\n",
+            config.base_url.trim_end_matches('/')
+        )
+        .as_bytes(),
+    )?;
+    Ok(())
+}
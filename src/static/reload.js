@@ -0,0 +1,24 @@
+<script>
+    (function () {
+        var events = new EventSource("/__wing/events");
+        events.onmessage = function (e) {
+            if (e.data.indexOf("css:") === 0) {
+                var changed = e.data.slice(4);
+                var found = false;
+                document.querySelectorAll("link[rel=stylesheet]").forEach(function (link) {
+                    var url = new URL(link.href);
+                    if (url.pathname === changed) {
+                        url.searchParams.set("wing", Date.now());
+                        link.href = url.toString();
+                        found = true;
+                    }
+                });
+                if (found === false) {
+                    window.location.reload();
+                }
+            } else if (e.data === "reload") {
+                window.location.reload();
+            }
+        };
+    })();
+</script>
@@ -0,0 +1,2 @@
+/// Taxonomies, e.g. tags, from page frontmatter.
+pub mod taxonomies;
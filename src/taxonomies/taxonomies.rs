@@ -0,0 +1,345 @@
+// std
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// external
+use serde::Serialize;
+use tera::{Context, Tera};
+
+// local
+use crate::links::links::{page_path, page_url, slugify};
+use crate::pagination::pagination::{paginate, WingPaginator};
+use crate::rss::rss;
+use crate::{
+    write_if_changed, write_page, WingConfig, WingError, WingPageSummary, WingSite, WingTemplate,
+    WingTemplateFrontmatter,
+};
+
+/// A taxonomy, and the terms used in it
+#[derive(Serialize, Clone, Debug)]
+pub struct WingTaxonomy {
+    /// Name of the taxonomy, e.g. `tags`
+    pub name: String,
+    /// Root-relative link to the taxonomy's page, listing its terms
+    pub link: String,
+    /// Canonical URL of the taxonomy's page
+    pub url: String,
+    /// Terms, sorted by slug
+    pub terms: Vec<WingTerm>,
+}
+
+/// A term in a taxonomy, e.g. a single tag
+#[derive(Serialize, Clone, Debug)]
+pub struct WingTerm {
+    /// The term, as written in frontmatter
+    pub name: String,
+    /// The term, as used in its page's location
+    pub slug: String,
+    /// Root-relative link to the term's page
+    pub link: String,
+    /// Canonical URL of the term's page
+    pub url: String,
+    /// Root-relative link to the term's RSS feed, if the taxonomy has feeds
+    pub feed: Option<String>,
+    /// Pages using the term, sorted by path
+    pub pages: Vec<WingPageSummary>,
+}
+
+/// Data available to `taxonomy_list.html` and `taxonomy_single.html`
+#[derive(Serialize)]
+struct WingTaxonomyData<'a> {
+    items: &'a Vec<WingPageSummary>,
+    taxonomies: &'a HashMap<String, WingTaxonomy>,
+    /// The taxonomy being rendered
+    taxonomy: &'a WingTaxonomy,
+    /// The term being rendered, only on `taxonomy_single.html`
+    term: Option<&'a WingTerm>,
+    /// On a paginated term's page, the pages listed on it and links to the others
+    paginator: Option<WingPaginator>,
+    url: String,
+}
+
+/// Gets the terms a page uses in a taxonomy: `tags`, or any other frontmatter key holding a list (or single string)
+pub fn page_terms(frontmatter: &WingTemplateFrontmatter, taxonomy: &str) -> Vec<String> {
+    if taxonomy == "tags" {
+        return frontmatter.tags.clone();
+    }
+
+    match frontmatter.extra.get(taxonomy) {
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect(),
+        Some(serde_json::Value::String(value)) => vec![value.clone()],
+        _ => vec![],
+    }
+}
+
+/// Location of a taxonomy's page, or a term's page, under the output directory
+fn location(taxonomy: &str, term: Option<&str>) -> Vec<String> {
+    let mut segments = vec![String::from(taxonomy)];
+    if let Some(term) = term {
+        segments.push(String::from(term));
+    }
+    segments.push(String::from("index.html"));
+    segments
+}
+
+/// Groups pages by the terms they use in each configured taxonomy.  Terms without any letters or digits are skipped, as they have no location, and different terms with the same location are an error.
+pub fn collect(
+    pages: &Vec<WingPageSummary>,
+    config: &WingConfig,
+) -> std::result::Result<HashMap<String, WingTaxonomy>, WingError> {
+    let mut taxonomies = HashMap::new();
+    for taxonomy in config.taxonomies.iter() {
+        let mut terms: HashMap<String, WingTerm> = HashMap::new();
+        for page in pages.iter() {
+            for name in page_terms(&page.frontmatter, &taxonomy.name) {
+                let slug = slugify(&name);
+                if slug.len() == 0 {
+                    continue;
+                }
+
+                if let Some(existing) = terms.get(&slug) {
+                    if existing.name.to_lowercase() != name.to_lowercase() {
+                        return Err(WingError::Frontmatter {
+                            path: Path::new(&config.content_dir).join(format!("{}.md", page.path)),
+                            line: None,
+                            message: format!(
+                                "{} \"{}\" and \"{}\" would both be built to /{}/{}/",
+                                taxonomy.name, existing.name, name, taxonomy.name, slug
+                            ),
+                        });
+                    }
+                }
+
+                let segments = location(&taxonomy.name, Some(&slug));
+                let term = terms.entry(slug.clone()).or_insert(WingTerm {
+                    name,
+                    link: page_path(&segments, config),
+                    url: page_url(&segments, config),
+                    feed: if taxonomy.feed == true {
+                        Some(format!("/{}/{}/rss.xml", taxonomy.name, slug))
+                    } else {
+                        None
+                    },
+                    slug,
+                    pages: vec![],
+                });
+                term.pages.push(page.clone());
+            }
+        }
+
+        let mut terms: Vec<WingTerm> = terms.into_iter().map(|(_slug, term)| term).collect();
+        terms.sort_by(|a, b| a.slug.cmp(&b.slug));
+
+        let segments = location(&taxonomy.name, None);
+        taxonomies.insert(
+            taxonomy.name.clone(),
+            WingTaxonomy {
+                name: taxonomy.name.clone(),
+                link: page_path(&segments, config),
+                url: page_url(&segments, config),
+                terms,
+            },
+        );
+    }
+
+    Ok(taxonomies)
+}
+
+/// Renders one of the taxonomy templates to `segments` in the output directory
+fn render(
+    tera: &Tera,
+    template: &str,
+    data: &WingTaxonomyData,
+    segments: &Vec<String>,
+    config: &WingConfig,
+    assets: &HashMap<String, String>,
+) -> std::result::Result<PathBuf, WingError> {
+    let template_file = format!("{}.html", template);
+    let context = Context::from_serialize(data).map_err(|e| WingError::template(None, &e))?;
+    let rendered = tera
+        .render(&template_file, &context)
+        .map_err(|e| WingError::template(None, &e))?;
+
+    let location = segments
+        .iter()
+        .fold(Path::new(&config.output_dir).to_path_buf(), |l, s| {
+            l.join(s)
+        });
+    write_page(&rendered, &location, config, assets)?;
+    Ok(location)
+}
+
+/// Renders each taxonomy's page with `taxonomy_list.html`, and each term's page with `taxonomy_single.html`, returning their locations
+pub fn generate(
+    tera: &Tera,
+    site: &WingSite,
+    config: &WingConfig,
+    assets: &HashMap<String, String>,
+) -> std::result::Result<Vec<PathBuf>, WingError> {
+    let mut locations = Vec::new();
+    for taxonomy_config in config.taxonomies.iter() {
+        let taxonomy = match site.taxonomies.get(&taxonomy_config.name) {
+            Some(t) => t,
+            None => continue,
+        };
+        let paginate_by = taxonomy_config.paginate_by.unwrap_or(config.paginate_by);
+
+        let mut data = WingTaxonomyData {
+            items: &site.pages,
+            taxonomies: &site.taxonomies,
+            taxonomy,
+            term: None,
+            paginator: None,
+            url: taxonomy.url.clone(),
+        };
+        let segments = location(&taxonomy.name, None);
+        locations.push(render(
+            tera,
+            "taxonomy_list",
+            &data,
+            &segments,
+            config,
+            assets,
+        )?);
+
+        for term in taxonomy.terms.iter() {
+            data.term = Some(term);
+            let segments = location(&taxonomy.name, Some(&term.slug));
+            let pagers = paginate(&term.pages, paginate_by, &segments, config);
+            if pagers.len() == 0 {
+                data.url = term.url.clone();
+                locations.push(render(
+                    tera,
+                    "taxonomy_single",
+                    &data,
+                    &segments,
+                    config,
+                    assets,
+                )?);
+            }
+
+            for (segments, paginator) in pagers.into_iter() {
+                data.url = page_url(&segments, config);
+                data.paginator = Some(paginator);
+                locations.push(render(
+                    tera,
+                    "taxonomy_single",
+                    &data,
+                    &segments,
+                    config,
+                    assets,
+                )?);
+            }
+        }
+    }
+
+    Ok(locations)
+}
+
+/// Writes an RSS feed for each term in taxonomies with `feed` set, returning their locations
+pub fn generate_feeds(
+    pages: &Vec<WingTemplate>,
+    site: &WingSite,
+    config: &WingConfig,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut locations = Vec::new();
+    for taxonomy in config.taxonomies.iter().filter(|t| t.feed == true) {
+        let taxonomy = match site.taxonomies.get(&taxonomy.name) {
+            Some(t) => t,
+            None => continue,
+        };
+
+        for term in taxonomy.terms.iter() {
+            let included: Vec<&WingTemplate> = pages
+                .iter()
+                .filter(|p| {
+                    p.section.is_none()
+                        && page_terms(&p.frontmatter, &taxonomy.name)
+                            .iter()
+                            .any(|t| slugify(t) == term.slug)
+                })
+                .collect();
+            let title = if config.feed.title.len() > 0 {
+                format!("{} - {}", config.feed.title, term.name)
+            } else {
+                term.name.clone()
+            };
+            let feed = rss::render(
+                &included,
+                config,
+                &title,
+                &config.feed.description,
+                &term.url,
+            );
+
+            let dir = Path::new(&config.output_dir)
+                .join(&taxonomy.name)
+                .join(&term.slug);
+            fs::create_dir_all(&dir)?;
+            write_if_changed(&dir.join("rss.xml"), feed.as_bytes())?;
+            locations.push(dir.join("rss.xml"));
+        }
+    }
+
+    Ok(locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect, page_terms};
+    use crate::links::links::slugify;
+    use crate::{WingConfig, WingPageSummary, WingTaxonomyConfig, WingTemplateFrontmatter};
+
+    fn page(path: &str, tags: &[&str]) -> WingPageSummary {
+        let mut page = WingPageSummary::test(path);
+        page.frontmatter.tags = tags.iter().map(|t| String::from(*t)).collect();
+        page
+    }
+
+    #[test]
+    pub fn test_terms() {
+        assert_eq!(slugify("Rust & Web"), "rust-web");
+        assert_eq!(slugify("  C++ "), "c");
+        assert_eq!(slugify("déjà vu"), "déjà-vu");
+
+        let mut frontmatter = WingTemplateFrontmatter {
+            tags: vec![String::from("a"), String::from("b")],
+            ..Default::default()
+        };
+        frontmatter
+            .extra
+            .insert(String::from("categories"), serde_json::json!(["News", 1]));
+        frontmatter
+            .extra
+            .insert(String::from("series"), serde_json::json!("Intro"));
+
+        assert_eq!(page_terms(&frontmatter, "tags"), vec!["a", "b"]);
+        assert_eq!(page_terms(&frontmatter, "categories"), vec!["News"]);
+        assert_eq!(page_terms(&frontmatter, "series"), vec!["Intro"]);
+        assert_eq!(page_terms(&frontmatter, "authors").len(), 0);
+
+        let config = WingConfig {
+            taxonomies: vec![WingTaxonomyConfig {
+                name: String::from("tags"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let pages = vec![page("a", &["Rust", "#"]), page("b", &["rust"])];
+        let taxonomies = collect(&pages, &config).unwrap();
+        let terms = &taxonomies["tags"].terms;
+        assert_eq!(terms.len(), 1);
+        assert_eq!(terms[0].slug, "rust");
+        assert_eq!(terms[0].pages.len(), 2);
+
+        let pages = vec![page("a", &["C#"]), page("b", &["C"])];
+        match collect(&pages, &config) {
+            Err(e) => assert!(e.to_string().contains("\"C#\" and \"C\"")),
+            Ok(_) => panic!("terms with the same location were merged"),
+        }
+    }
+}
@@ -0,0 +1,2 @@
+/// Heading anchors and tables of contents.
+pub mod toc;
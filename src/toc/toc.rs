@@ -0,0 +1,154 @@
+// std
+use std::collections::HashSet;
+
+// external
+use pulldown_cmark::{CowStr, Event, Tag};
+use serde::Serialize;
+
+// local
+use crate::links::links::slugify;
+
+/// A heading in a page, for its table of contents
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct WingHeading {
+    /// Level of the heading, from `1` (`<h1>`) to `6`
+    pub level: u32,
+    /// Text of the heading
+    pub title: String,
+    /// `id` of the heading, to link to it with `#id`
+    pub id: String,
+    /// Headings below this one, before the next heading at the same level or above
+    pub children: Vec<WingHeading>,
+}
+
+/// Gives a heading an `id` that isn't used elsewhere in the page, e.g. `intro`, then `intro-1`
+fn unique_id(title: &str, used: &mut HashSet<String>) -> String {
+    let base = match slugify(title) {
+        s if s.len() > 0 => s,
+        _ => String::from("section"),
+    };
+
+    let mut id = base.clone();
+    let mut n = 1;
+    while used.contains(&id) == true {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+
+    used.insert(id.clone());
+    id
+}
+
+/// Nests headings under the nearest heading above them with a lower level
+fn nest(flat: Vec<WingHeading>) -> Vec<WingHeading> {
+    let mut toc: Vec<WingHeading> = Vec::new();
+    // headings that later ones can still be nested under, from the top level down
+    let mut open: Vec<WingHeading> = Vec::new();
+    for heading in flat.into_iter() {
+        while open.last().map_or(false, |h| h.level >= heading.level) {
+            let closed = open.pop().unwrap();
+            match open.last_mut() {
+                Some(parent) => parent.children.push(closed),
+                None => toc.push(closed),
+            }
+        }
+        open.push(heading);
+    }
+
+    while let Some(closed) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(closed),
+            None => toc.push(closed),
+        }
+    }
+
+    toc
+}
+
+/// Gives each heading an `id` (and a link to itself, if `anchors` is `true`), returning the changed events and the page's table of contents
+pub fn headings<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    anchors: bool,
+) -> (Vec<Event<'a>>, Vec<WingHeading>) {
+    let mut output = Vec::new();
+    let mut flat = Vec::new();
+    let mut used = HashSet::new();
+    // events and text of the heading being read
+    let mut heading: Option<(Vec<Event<'a>>, String)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(_level)) => heading = Some((Vec::new(), String::new())),
+            Event::End(Tag::Heading(level)) => {
+                let (inner, title) = heading.take().unwrap_or_default();
+                let id = unique_id(&title, &mut used);
+
+                output.push(Event::Html(CowStr::from(format!(
+                    "<h{} id=\"{}\">",
+                    level, id
+                ))));
+                output.extend(inner);
+                if anchors == true {
+                    output.push(Event::Html(CowStr::from(format!(
+                        " <a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>",
+                        id
+                    ))));
+                }
+                output.push(Event::Html(CowStr::from(format!("</h{}>\n", level))));
+
+                flat.push(WingHeading {
+                    level,
+                    title: String::from(title.trim()),
+                    id,
+                    children: vec![],
+                });
+            }
+            e => match heading.as_mut() {
+                Some((inner, title)) => {
+                    if let Event::Text(text) | Event::Code(text) = &e {
+                        title.push_str(text);
+                    }
+                    inner.push(e);
+                }
+                None => output.push(e),
+            },
+        }
+    }
+
+    (output, nest(flat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::headings;
+    use pulldown_cmark::{html, Parser};
+
+    #[test]
+    pub fn test_headings() {
+        let markdown =
+            "# Intro\n\n## Setup `wsg`\n\n### Linux\n\n## Setup wsg\n\n# Intro\n\n#### !!!\n";
+        let (events, toc) = headings(Parser::new(markdown), true);
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+
+        assert!(output.starts_with(
+            "<h1 id=\"intro\">Intro <a class=\"anchor\" href=\"#intro\" aria-hidden=\"true\">#</a></h1>\n"
+        ));
+        assert!(output.contains("<h2 id=\"setup-wsg\">Setup <code>wsg</code> <a"));
+        assert!(output.contains("<h2 id=\"setup-wsg-1\">"));
+        assert!(output.contains("<h1 id=\"intro-1\">"));
+        assert!(output.contains("<h4 id=\"section\">"));
+
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Intro");
+        assert_eq!(toc[0].children.len(), 2);
+        assert_eq!(toc[0].children[0].title, "Setup wsg");
+        assert_eq!(toc[0].children[0].children[0].id, "linux");
+        assert_eq!(toc[1].id, "intro-1");
+        assert_eq!(toc[1].children[0].level, 4);
+
+        let (events, _toc) = headings(Parser::new("## Plain\n"), false);
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        assert_eq!(output, "<h2 id=\"plain\">Plain</h2>\n");
+    }
+}